@@ -18,6 +18,7 @@ pub enum ActionEvent {
     Move(Vec2),
     AttackMove(Vec2),
     Stop,
+    Skillshot(Vec2),
 }
 
 fn init() {}
@@ -37,6 +38,9 @@ fn update(
         if keyboard_buttons.just_pressed(KeyCode::KeyA) {
             action_events.send(ActionEvent::AttackMove(point));
         }
+        if keyboard_buttons.just_pressed(KeyCode::KeyQ) {
+            action_events.send(ActionEvent::Skillshot(point));
+        }
     }
     if keyboard_buttons.just_pressed(KeyCode::KeyS) {
         action_events.send(ActionEvent::Stop);
@@ -6,15 +6,35 @@ pub mod output;
 pub use output::*;
 
 use bevy::{
-    app::PluginGroupBuilder, dev_tools::fps_overlay::FpsOverlayPlugin,
-    pbr::wireframe::WireframePlugin, prelude::*, window::*, winit::WinitWindows,
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    dev_tools::fps_overlay::FpsOverlayPlugin,
+    log::LogPlugin,
+    pbr::wireframe::WireframePlugin,
+    prelude::*,
+    state::app::StatesPlugin,
+    window::*,
+    winit::WinitWindows,
 };
 use bevy_framepace::*;
-use std::sync::LazyLock;
+use std::{sync::LazyLock, time::Duration};
 use winit::window::Icon;
 
 fn main() -> AppExit {
-    App::new().add_plugins(MobaPlugin).run()
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == DETERMINISM_TEST_ARG) {
+        let Some(ticks) = args.get(i + 1).and_then(|ticks| ticks.parse().ok()) else {
+            eprintln!("usage: {} <ticks>", DETERMINISM_TEST_ARG);
+            return AppExit::error();
+        };
+        return run_determinism_test(ticks);
+    }
+    let mut app = App::new();
+    if args.iter().any(|arg| arg == HEADLESS_ARG) {
+        app.add_plugins((LogPlugin::default(), HeadlessMobaPlugin));
+    } else {
+        app.add_plugins(MobaPlugin);
+    }
+    app.run()
 }
 
 pub struct MobaPlugin;
@@ -30,9 +50,9 @@ impl Plugin for MobaPlugin {
         //core ordering configuration
         app.configure_sets(PreUpdate, InputSet.after(bevy::input::InputSystem));
         app.configure_sets(PreUpdate, (AxisSet, ActionSet).chain());
-        app.configure_sets(FixedUpdate, (PlayerSet, LogicSet, PhysicsSet).chain());
+        configure_simulation(app);
         //specific ordering dependencies
-        app.configure_sets(Startup, PlayerSet.after(LogicSet));
+        app.configure_sets(Update, InterpolationSet.before(OrbitCameraSet));
         app.configure_sets(Update, (GizmoSet, HealthbarSet).after(OrbitCameraSet));
         //utility system sets
         app.configure_sets(
@@ -42,6 +62,62 @@ impl Plugin for MobaPlugin {
     }
 }
 
+//runs the fixedupdate simulation only, for servers and ci machines without a display
+pub struct HeadlessMobaPlugin;
+impl Plugin for HeadlessMobaPlugin {
+    fn build(&self, app: &mut App) {
+        //plugins
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(
+            Duration::from_secs_f64(1. / UPDATE_FIXED_RATE),
+        )));
+        app.add_plugins((StatesPlugin, AssetPlugin::default()));
+        app.add_plugins(SimulationPlugins);
+        //core ordering configuration
+        configure_simulation(app);
+        //nothing to show the result on, so stop once the match is decided
+        app.add_systems(OnEnter(GameState::GameOver), exit);
+    }
+}
+
+//settings and ordering shared by windowed and headless apps
+fn configure_simulation(app: &mut App) {
+    //set fixed-update rate
+    app.insert_resource(Time::<Fixed>::from_hz(UPDATE_FIXED_RATE));
+    app.configure_sets(
+        FixedUpdate,
+        (
+            NetworkSet,
+            ReplaySet,
+            PlayerSet,
+            ProjectileSet,
+            LogicSet,
+            PhysicsSet,
+            VisionSet,
+            ChecksumSet,
+            GameStateSet,
+        )
+            .chain()
+            .run_if(simulation_running),
+    );
+    //ticks are skipped while waiting on other peers' commands
+    app.configure_sets(
+        FixedUpdate,
+        (
+            ReplaySet,
+            PlayerSet,
+            ProjectileSet,
+            LogicSet,
+            PhysicsSet,
+            VisionSet,
+            ChecksumSet,
+            GameStateSet,
+        )
+            .run_if(lockstep_ready),
+    );
+    app.configure_sets(FixedUpdate, NavigationSet.in_set(LogicSet));
+    app.configure_sets(OnEnter(GameState::InGame), PlayerSet.after(LogicSet));
+}
+
 const GAME_NAME: &str = "Moba MVP";
 const GAME_ICON: &str = "assets/textures/moba_icon.png";
 static GAME_WINDOW: LazyLock<Window> = LazyLock::new(|| Window {
@@ -59,6 +135,8 @@ static GAME_WINDOW: LazyLock<Window> = LazyLock::new(|| Window {
 });
 const UPDATE_MAX_RATE: Option<f64> = None;
 const UPDATE_FIXED_RATE: f64 = 16.;
+const HEADLESS_ARG: &str = "--headless";
+const DETERMINISM_TEST_ARG: &str = "--determinism-test";
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MainSet;
@@ -90,22 +168,40 @@ impl PluginGroup for GamePlugins {
             .add(AxisPlugin)
             .add(ActionPlugin)
             //fixedupdate
-            .add(PlayerPlugin)
-            .add(LogicPlugin)
-            .add(PhysicsPlugin)
+            .add_group(SimulationPlugins)
             //update
+            .add(InterpolationPlugin)
             .add(OrbitCameraPlugin)
             .add(OverlayCameraPlugin)
             .add(MapPlugin)
             .add(ModelPlugin)
             .add(HealthbarPlugin)
             .add(GizmoPlugin)
+            .add(ResultPlugin)
+    }
+}
+
+pub struct SimulationPlugins;
+impl PluginGroup for SimulationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(GameStatePlugin)
+            .add(DefinitionPlugin)
+            .add(NetworkPlugin)
+            .add(ReplayPlugin)
+            .add(TerrainPlugin)
+            .add(PlayerPlugin)
+            .add(LogicPlugin)
+            .add(NavigationPlugin)
+            .add(ProjectilePlugin)
+            .add(PhysicsPlugin)
+            .add(VisionPlugin)
+            .add(ChecksumPlugin)
     }
 }
 
 fn init(
     mut settings: ResMut<FramepaceSettings>,
-    mut commands: Commands,
     mut window_query: Query<&mut Window, With<PrimaryWindow>>,
     windows: NonSend<WinitWindows>,
 ) {
@@ -127,8 +223,10 @@ fn init(
     if let Some(max_rate) = UPDATE_MAX_RATE {
         settings.limiter = Limiter::from_framerate(max_rate);
     }
-    //set fixed-update rate
-    commands.insert_resource(Time::<Fixed>::from_hz(UPDATE_FIXED_RATE));
+}
+
+fn exit(mut writer: EventWriter<AppExit>) {
+    writer.send(AppExit::Success);
 }
 
 fn update(keyboard: Res<ButtonInput<KeyCode>>, mut writer: EventWriter<AppExit>) {
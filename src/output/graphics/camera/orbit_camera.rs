@@ -113,7 +113,7 @@ fn update(
     mut camera_query: Query<(&mut Transform, &mut GlobalTransform, &mut OrbitDistance)>,
     mut flip_orientation: Local<FlipOrientation>,
     time: Res<Time>,
-    player: Res<player::Player>,
+    player: Option<Res<player::Player>>,
     player_query: Query<&Transform, Without<OrbitDistance>>,
 ) {
     //get camera transform
@@ -134,8 +134,9 @@ fn update(
         }
     }
     if keyboard_buttons.pressed(KeyCode::Space) {
-        let player = player_query.get(player.0).unwrap();
-        orbit_transform.translation = player.translation.truncate();
+        if let Some(Ok(player)) = player.map(|player| player_query.get(player.0)) {
+            orbit_transform.translation = player.translation.truncate();
+        }
     }
 
     //adjust pitch
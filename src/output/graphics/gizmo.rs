@@ -7,7 +7,12 @@ impl Plugin for GizmoPlugin {
         app.add_systems(Startup, init.in_set(GizmoSet));
         app.add_systems(
             Update,
-            (update_player, update_wireframe, update_cursor3d).in_set(GizmoSet),
+            (
+                update_player.run_if(resource_exists::<player::Player>),
+                update_wireframe,
+                update_cursor3d,
+            )
+                .in_set(GizmoSet),
         );
     }
 }
@@ -35,7 +40,9 @@ fn update_player(
     player: Res<player::Player>,
     player_query: Query<(&Transform, &MovePosition)>,
 ) {
-    let (transform, move_position) = player_query.get(player.0).unwrap();
+    let Ok((transform, move_position)) = player_query.get(player.0) else {
+        return;
+    };
     let start = transform.translation.with_z(25.);
     let diff = transform.rotation.mul_vec3(Vec3::X * 30.);
     gizmos.arrow(start, start + diff, Color::WHITE);
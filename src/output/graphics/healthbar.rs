@@ -1,5 +1,6 @@
 use crate::*;
 use bevy::prelude::*;
+use serde::Deserialize;
 use std::sync::LazyLock;
 
 pub struct HealthbarPlugin;
@@ -39,7 +40,7 @@ fn font_size_scale(available_height: f32) -> (f32, f32) {
     (size, scale)
 }
 
-#[derive(Component, Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
 pub enum DisplayHealthbar {
     #[default]
     Basic,
@@ -49,6 +50,9 @@ pub enum DisplayHealthbar {
 #[derive(Component)]
 struct HealthTextTag;
 
+#[derive(Component)]
+struct HealthFillTag;
+
 #[derive(Component)]
 struct HealthbarAnchor(Entity);
 
@@ -85,15 +89,18 @@ fn add_healthbars(
             ));
         healthbar_entity.with_children(|builder| {
             //red bar
-            builder.spawn(NodeBundle {
-                style: Style {
-                    width: Val::Percent(health_ratio * 100.),
-                    height: Val::Percent(100.),
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(health_ratio * 100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(color),
                     ..default()
                 },
-                background_color: BackgroundColor(color),
-                ..default()
-            });
+                HealthFillTag,
+            ));
         });
         if *healthbar == DisplayHealthbar::Advanced {
             healthbar_entity.with_children(|builder| {
@@ -169,15 +176,27 @@ fn add_healthbars(
     }
 }
 
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn update_healthbars(
+    mut commands: Commands,
     mut healthbar_query: Query<
         (Entity, &mut Style, &HealthbarAnchor, &mut Visibility),
-        Without<HealthTextTag>,
+        (Without<HealthTextTag>, Without<HealthFillTag>),
     >,
     display_query: Query<
-        (&DisplayHealthbar, &DisplayModel, &Radius, &Transform),
+        (
+            &DisplayHealthbar,
+            &DisplayModel,
+            &Radius,
+            &Transform,
+            &Health,
+            &MaxHealth,
+            &Visible,
+        ),
         Without<HealthTextTag>,
     >,
+    player_team: Option<Res<player::PlayerTeam>>,
+    mut fill_query: Query<&mut Style, (With<HealthFillTag>, Without<HealthbarAnchor>)>,
     camera_query: Query<
         (&Camera, &Transform, &GlobalTransform),
         (With<OrbitDistance>, Without<HealthTextTag>),
@@ -190,8 +209,22 @@ fn update_healthbars(
     for (healthbar_entity, mut healthbar_style, healthbar_anchor, mut healthbar_visibility) in
         &mut healthbar_query
     {
-        let (display_healthbar, display_model, display_radius, display_transform) =
-            display_query.get(healthbar_anchor.0).unwrap();
+        let (
+            display_healthbar,
+            display_model,
+            display_radius,
+            display_transform,
+            display_health,
+            display_max_health,
+            display_visible,
+        ) = match display_query.get(healthbar_anchor.0) {
+            Ok(display) => display,
+            Err(_) => {
+                //anchor has been despawned
+                commands.entity(healthbar_entity).despawn_recursive();
+                continue;
+            }
+        };
         //choose precise anchor point based on anchor position and camera orientation
         let height = display_model.get_height(display_radius.0);
         let anchor_point =
@@ -199,7 +232,10 @@ fn update_healthbars(
         //check healthbar anchor point is both within camera frustum and within cull range
         let pixel = position_to_pixel(anchor_point, camera, global_camera_transform);
         let distance_from_camera = (camera_transform.translation - anchor_point).length();
-        if distance_from_camera >= HEALTHBAR_CULL_DISTANCE || pixel == None {
+        let hidden_by_fog = player_team
+            .as_ref()
+            .is_some_and(|team| !display_visible.to(team.0));
+        if distance_from_camera >= HEALTHBAR_CULL_DISTANCE || pixel == None || hidden_by_fog {
             //hide healthbar and text
             if *healthbar_visibility == Visibility::Visible {
                 *healthbar_visibility = Visibility::Hidden;
@@ -216,6 +252,12 @@ fn update_healthbars(
             healthbar_style.height = Val::Px(size.y);
             healthbar_style.left = Val::Px(pixel.x - size.x / 2.);
             healthbar_style.top = Val::Px(pixel.y - size.y);
+            //set health fill
+            for child in children_query.iter_descendants(healthbar_entity) {
+                if let Ok(mut fill_style) = fill_query.get_mut(child) {
+                    fill_style.width = Val::Percent(display_health.0 / display_max_health.0 * 100.);
+                }
+            }
             //set text size
             if *display_healthbar == DisplayHealthbar::Advanced {
                 let child = children_query
@@ -224,6 +266,7 @@ fn update_healthbars(
                     .next()
                     .unwrap();
                 let (mut text, mut transform) = text_query.get_mut(child).unwrap();
+                text.sections[0].value = format!("{}", display_health.0.ceil());
                 let (font_size, font_scale) = font_size_scale(size.y);
                 text.sections[0].style.font_size = font_size;
                 transform.scale = Vec3::splat(font_scale);
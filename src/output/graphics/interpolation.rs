@@ -0,0 +1,57 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub struct InterpolationPlugin;
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedFirst, update_previous.in_set(InterpolationSet));
+        app.add_systems(Update, (init, update).chain().in_set(InterpolationSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InterpolationSet;
+
+//the unit's position and facing before the latest fixed tick
+#[derive(Component)]
+pub struct Interpolated {
+    position: Vec2,
+    facing: Vec2,
+}
+
+fn init(mut commands: Commands, query: Query<(Entity, &Position, &Facing), Added<Position>>) {
+    for (entity, position, facing) in &query {
+        commands.entity(entity).insert(Interpolated {
+            position: position.get(),
+            facing: facing.0,
+        });
+    }
+}
+
+fn update_previous(mut query: Query<(&Position, &Facing, &mut Interpolated)>) {
+    for (position, facing, mut interpolated) in &mut query {
+        interpolated.position = position.get();
+        interpolated.facing = facing.0;
+    }
+}
+
+//the only place simulated placement reaches transforms, rendered one tick behind and blended
+//towards the latest tick by how far we are into the next one
+fn update(
+    mut query: Query<(&mut Transform, &Position, &Facing, Option<&Interpolated>)>,
+    time: Res<Time<Fixed>>,
+) {
+    let fraction = time.overstep_fraction();
+    for (mut transform, position, facing, interpolated) in &mut query {
+        let rotation = Quat::from_rotation_z(facing.0.to_angle());
+        let (position, rotation) = match interpolated {
+            Some(previous) => (
+                previous.position.lerp(position.get(), fraction),
+                Quat::from_rotation_z(previous.facing.to_angle()).slerp(rotation, fraction),
+            ),
+            None => (position.get(), rotation),
+        };
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotation = rotation;
+    }
+}
@@ -1,10 +1,14 @@
-//use crate::*;
+use crate::*;
 use bevy::prelude::*;
 
 pub struct MapPlugin;
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, init.in_set(MapSet));
+        app.add_systems(
+            Update,
+            init.run_if(resource_exists_and_changed::<Terrain>)
+                .in_set(MapSet),
+        );
         app.add_systems(Update, update.in_set(MapSet));
     }
 }
@@ -20,10 +24,24 @@ const DESIRED_WALL_HEIGHT: f32 = 30.;
 #[derive(Component, Default)]
 struct Map;
 
-fn init(mut commands: Commands, server: Res<AssetServer>) {
+//the terrain is only built once the map layout has loaded, so the scene is shown alongside it
+//and swapped along with it when the layout names another model
+fn init(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    map: Res<CurrentMap>,
+    layouts: Res<Assets<MapLayout>>,
+    mut map_query: Query<&mut Handle<Scene>, With<Map>>,
+) {
+    let layout = map.get(&layouts).unwrap();
+    let scene = server.load(format!("{}#Scene0", layout.model));
+    if let Ok(mut handle) = map_query.get_single_mut() {
+        handle.set_if_neq(scene);
+        return;
+    }
     commands.spawn((
         SceneBundle {
-            scene: server.load("models/map.glb#Scene0"),
+            scene,
             transform: Transform::from_scale(
                 Vec3::ONE.with_z(DESIRED_WALL_HEIGHT / BLENDER_WALL_HEIGHT),
             ),
@@ -4,7 +4,11 @@ pub mod gizmo;
 pub use gizmo::*;
 pub mod healthbar;
 pub use healthbar::*;
+pub mod interpolation;
+pub use interpolation::*;
 pub mod map;
 pub use map::*;
 pub mod model;
 pub use model::*;
+pub mod result;
+pub use result::*;
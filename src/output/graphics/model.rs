@@ -1,6 +1,7 @@
 use crate::*;
 use bevy::{color::palettes::css, pbr::wireframe::Wireframe, prelude::*};
 use ordered_float::OrderedFloat;
+use serde::Deserialize;
 use std::{collections::HashMap, f32::consts::PI};
 
 pub struct ModelPlugin;
@@ -9,7 +10,14 @@ impl Plugin for ModelPlugin {
         app.init_resource::<MaterialMap>();
         app.init_resource::<MeshMap>();
         app.add_systems(Startup, init.in_set(ModelSet));
-        app.add_systems(Update, update.in_set(ModelSet));
+        app.add_systems(
+            Update,
+            (
+                update,
+                update_visibility.run_if(resource_exists::<player::PlayerTeam>),
+            )
+                .in_set(ModelSet),
+        );
     }
 }
 
@@ -166,7 +174,7 @@ impl Into<Mesh> for HashableMesh {
         }
     }
 }
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Deserialize)]
 enum HashableMeshType {
     Capsule,
     Cylinder,
@@ -174,12 +182,14 @@ enum HashableMeshType {
     Cuboid,
 }
 
-//models
-#[derive(Component, Copy, Clone)]
+//models, unit definitions describe these with any fields left out taking their defaults
+#[derive(Component, Copy, Clone, Deserialize)]
+#[serde(default)]
 pub struct DisplayModel {
     mesh_type: HashableMeshType,
     half_height_ratio: f32,
     raised: bool,
+    elevation: f32,
     wireframe: bool,
 }
 impl Default for DisplayModel {
@@ -188,6 +198,7 @@ impl Default for DisplayModel {
             mesh_type: HashableMeshType::default(),
             half_height_ratio: 1.0,
             raised: true,
+            elevation: 0.,
             wireframe: false,
         }
     }
@@ -223,6 +234,9 @@ impl DisplayModel {
             ..self
         }
     }
+    pub fn with_elevation(self, elevation: f32) -> Self {
+        Self { elevation, ..self }
+    }
     pub fn wireframed(self) -> Self {
         Self {
             wireframe: true,
@@ -236,26 +250,44 @@ impl DisplayModel {
         }
     }
     pub fn get_height(&self, radius: f32) -> f32 {
-        self.half_height_ratio * radius * if self.raised { 2. } else { 1. }
+        self.elevation + self.half_height_ratio * radius * if self.raised { 2. } else { 1. }
     }
 }
 
+//the mesh entity currently shown for a DisplayModel
+#[derive(Component)]
+struct ModelMesh(Entity);
+
 fn init(mut commands: Commands, server: Res<AssetServer>) {
     commands.insert_resource(DevTexture(
         server.load("textures/untracked/kenney_dev_textures/Light/texture_07.png"),
     ));
 }
 
+//models are built when added and rebuilt when their definition is reloaded
+#[allow(clippy::type_complexity)]
 fn update(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut DisplayModel, &Radius, Option<&Team>), Added<DisplayModel>>,
+    query: Query<
+        (
+            Entity,
+            &DisplayModel,
+            &Radius,
+            Option<&Team>,
+            Option<&ModelMesh>,
+        ),
+        Or<(Changed<DisplayModel>, Changed<Radius>)>,
+    >,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut material_map: ResMut<MaterialMap>,
     mut mesh_map: ResMut<MeshMap>,
     dev_texture: Res<DevTexture>,
 ) {
-    for (entity, display, radius, team) in &mut query {
+    for (entity, display, radius, team, old_mesh) in &query {
+        if let Some(old_mesh) = old_mesh {
+            commands.entity(old_mesh.0).despawn_recursive();
+        }
         let half_height = display.half_height_ratio * radius.0;
         let mesh = HashableMesh::new(display.mesh_type, radius.0, half_height);
         let material_color = team_color(team.copied());
@@ -268,11 +300,10 @@ fn update(
                 material_color,
                 Some(material_texture),
             ),
-            transform: Transform::from_translation(Vec3::ZERO.with_z(if display.raised {
-                half_height
-            } else {
-                0.
-            }))
+            transform: Transform::from_translation(
+                Vec3::ZERO
+                    .with_z(display.elevation + if display.raised { half_height } else { 0. }),
+            )
             .with_rotation(Quat::from_rotation_x(
                 if display.mesh_type == HashableMeshType::Cuboid {
                     0.
@@ -287,6 +318,23 @@ fn update(
             model.insert(Wireframe);
         }
         let model_id = model.id();
-        commands.entity(entity).add_child(model_id);
+        commands
+            .entity(entity)
+            .add_child(model_id)
+            .insert(ModelMesh(model_id));
+    }
+}
+
+//hides units the player's team has no vision of
+fn update_visibility(
+    mut query: Query<(&Visible, &mut Visibility)>,
+    player_team: Res<player::PlayerTeam>,
+) {
+    for (visible, mut visibility) in &mut query {
+        visibility.set_if_neq(if visible.to(player_team.0) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
     }
 }
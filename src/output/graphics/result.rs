@@ -0,0 +1,80 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub struct ResultPlugin;
+impl Plugin for ResultPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), init.in_set(ResultSet));
+        app.add_systems(
+            Update,
+            update
+                .run_if(in_state(GameState::GameOver))
+                .in_set(ResultSet),
+        );
+        app.add_systems(OnExit(GameState::GameOver), cleanup.in_set(ResultSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResultSet;
+
+const RESULT_FONT_SIZE: f32 = 72.;
+const RESULT_HINT_FONT_SIZE: f32 = 30.;
+const RESTART_KEY: KeyCode = KeyCode::Enter;
+
+#[derive(Component)]
+struct ResultTag;
+
+fn init(mut commands: Commands, result: Res<GameResult>) {
+    let (text, color) = match result.winner {
+        Some(Team::Red) => ("Red team wins", team_color(Some(Team::Red))),
+        Some(Team::Blue) => ("Blue team wins", team_color(Some(Team::Blue))),
+        None => ("Draw", team_color(None)),
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ResultTag,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: RESULT_FONT_SIZE,
+                    color,
+                    ..default()
+                },
+            ));
+            builder.spawn(TextBundle::from_section(
+                "Press Enter to play again",
+                TextStyle {
+                    font_size: RESULT_HINT_FONT_SIZE,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn update(keyboard: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(RESTART_KEY) {
+        next_state.set(GameState::Loading);
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<ResultTag>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
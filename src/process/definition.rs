@@ -0,0 +1,397 @@
+use crate::*;
+use bevy::{
+    asset::{
+        io::{AssetSourceId, Reader},
+        AssetLoader, AsyncReadExt, LoadContext, LoadState,
+    },
+    prelude::*,
+    tasks::{block_on, futures_lite::StreamExt, poll_once, IoTaskPool, Task},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, marker::PhantomData, ops::Deref, path::Path};
+
+pub struct DefinitionPlugin;
+impl Plugin for DefinitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<UnitDefinition>();
+        app.init_asset::<MapLayout>();
+        app.register_asset_loader(RonLoader::<UnitDefinition>::new(UNIT_DEFINITION_EXTENSION));
+        app.register_asset_loader(RonLoader::<MapLayout>::new(MAP_LAYOUT_EXTENSION));
+        app.add_systems(Startup, init.in_set(DefinitionSet));
+        app.add_systems(
+            Update,
+            (
+                update_loading.run_if(in_state(GameState::Loading)),
+                update_reloads.run_if(playing_alone),
+            )
+                .in_set(DefinitionSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DefinitionSet;
+
+//every file in this folder defines one unit, named after the file
+const UNIT_DEFINITION_FOLDER: &str = "units";
+const UNIT_DEFINITION_EXTENSION: &str = "unit.ron";
+const MAP_LAYOUT_PATH: &str = "maps/default.map.ron";
+const MAP_LAYOUT_EXTENSION: &str = "map.ron";
+
+//stats and looks of a kind of unit, e.g. assets/units/tower.unit.ron
+#[derive(Asset, TypePath, Deserialize, Clone)]
+pub struct UnitDefinition {
+    pub radius: f32,
+    pub model: DisplayModel,
+    pub health: f32,
+    pub healthbar: DisplayHealthbar,
+    #[serde(default)]
+    pub sight: Option<f32>,
+    #[serde(default)]
+    pub move_speed: Option<f32>,
+    #[serde(default)]
+    pub attack: Option<AttackDefinition>,
+    #[serde(default)]
+    pub skillshot: Option<SkillshotDefinition>,
+}
+impl UnitDefinition {
+    //the optional fields the archetype's bundle needs which this definition leaves out
+    pub fn missing_fields(&self, archetype: Archetype) -> Vec<&'static str> {
+        let (sight, move_speed, attack, skillshot) = match archetype {
+            Archetype::Core | Archetype::Spawner => (true, false, false, false),
+            Archetype::Tower => (true, false, true, false),
+            Archetype::Advocate => (true, true, true, true),
+            Archetype::Minion => (true, true, true, false),
+            Archetype::Monster | Archetype::Demon => (false, true, true, false),
+        };
+        [
+            ("sight", sight && self.sight.is_none()),
+            ("move_speed", move_speed && self.move_speed.is_none()),
+            ("attack", attack && self.attack.is_none()),
+            ("skillshot", skillshot && self.skillshot.is_none()),
+        ]
+        .into_iter()
+        .filter_map(|(field, missing)| missing.then_some(field))
+        .collect()
+    }
+}
+
+//every definition a match spawns units from, with the archetype they're spawned as
+fn spawned_units() -> [(&'static str, Archetype); 8] {
+    [
+        (CORE_UNIT_NAME, Archetype::Core),
+        (SPAWNER_UNIT_NAME, Archetype::Spawner),
+        (TOWER_UNIT_NAME, Archetype::Tower),
+        (ADVOCATE_UNIT_NAME, Archetype::Advocate),
+        (MinionKind::Melee.unit_name(), Archetype::Minion),
+        (MinionKind::Ranged.unit_name(), Archetype::Minion),
+        (CampKind::Monster.unit_name(), Archetype::Monster),
+        (CampKind::Demon.unit_name(), Archetype::Demon),
+    ]
+}
+
+//a loaded unit definition along with its asset id, which units spawned from it keep
+#[derive(Clone, Copy)]
+pub struct UnitDefinitionRef<'a> {
+    pub id: AssetId<UnitDefinition>,
+    pub name: &'a str,
+    definition: &'a UnitDefinition,
+}
+impl UnitDefinitionRef<'_> {
+    //fields are checked when loading, so this only trips on definitions edited while the game runs
+    fn required<T: Copy>(&self, field: Option<T>, field_name: &str) -> T {
+        field.unwrap_or_else(|| {
+            panic!(
+                "{}/{}.{} has no {}, which this unit needs to spawn",
+                UNIT_DEFINITION_FOLDER, self.name, UNIT_DEFINITION_EXTENSION, field_name
+            )
+        })
+    }
+    pub fn sight_radius(&self) -> SightRadius {
+        SightRadius(self.required(self.sight, "sight"))
+    }
+    pub fn move_bundle(&self) -> MoveBundle {
+        MoveBundle::new(self.required(self.move_speed, "move_speed"))
+    }
+    pub fn attack_bundle(&self) -> AttackBundle {
+        let attack = self.required(self.attack, "attack");
+        let bundle = AttackBundle::new(attack.range, attack.damage, attack.speed);
+        match attack.projectile_speed {
+            Some(projectile_speed) => bundle.with_projectile(projectile_speed),
+            None => bundle,
+        }
+    }
+    pub fn skillshot(&self) -> Skillshot {
+        let skillshot = self.required(self.skillshot, "skillshot");
+        Skillshot::new(
+            skillshot.damage,
+            skillshot.speed,
+            skillshot.range,
+            skillshot.cooldown,
+        )
+    }
+}
+impl Deref for UnitDefinitionRef<'_> {
+    type Target = UnitDefinition;
+    fn deref(&self) -> &UnitDefinition {
+        self.definition
+    }
+}
+
+//the definition a unit was spawned from, so edits to it can be applied to the unit
+#[derive(Component, Clone, Copy)]
+pub struct DefinedBy(pub AssetId<UnitDefinition>);
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct AttackDefinition {
+    pub range: f32,
+    pub damage: f32,
+    pub speed: f32,
+    #[serde(default)]
+    pub projectile_speed: Option<f32>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct SkillshotDefinition {
+    pub damage: f32,
+    pub speed: f32,
+    pub range: f32,
+    pub cooldown: f64, //seconds
+}
+
+//placements of a map in team-local space, mirrored for each team, e.g. assets/maps/default.map.ron
+#[derive(Asset, TypePath, Deserialize)]
+pub struct MapLayout {
+    pub model: String, //map gltf the terrain and scene come from, relative to the assets folder
+    pub core: Vec2,
+    pub spawners: Vec<(Lane, Vec2)>,
+    pub towers: Vec<Vec2>,
+    pub advocates: Vec<Vec2>,
+    pub camps: Vec<CampLayout>,
+    pub lane_routes: [Vec<Vec2>; 3], //waypoints to the enemy core, indexed by Lane
+}
+
+#[derive(Deserialize)]
+pub struct CampLayout {
+    pub kind: CampKind,
+    pub position: Vec2,
+    pub respawn: f64, //seconds from the camp's unit dying until the next one spawns
+}
+
+//loads assets straight from ron files with the given extension
+struct RonLoader<A> {
+    extensions: [&'static str; 1],
+    marker: PhantomData<fn() -> A>,
+}
+impl<A> RonLoader<A> {
+    fn new(extension: &'static str) -> Self {
+        Self {
+            extensions: [extension],
+            marker: PhantomData,
+        }
+    }
+}
+impl<A: Asset + for<'de> Deserialize<'de>> AssetLoader for RonLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<A, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+//the definition files are listed and loaded one by one rather than with load_folder,
+//so one broken file is reported by itself instead of failing the whole folder
+#[derive(Resource)]
+enum UnitDefinitionFolder {
+    Listing(Task<Vec<Handle<UnitDefinition>>>),
+    Loading(Vec<Handle<UnitDefinition>>),
+}
+
+//unit definitions by name, inserted once they've all loaded
+#[derive(Resource)]
+pub struct UnitDefinitions(pub HashMap<String, Handle<UnitDefinition>>);
+impl UnitDefinitions {
+    pub fn get<'a>(
+        &self,
+        assets: &'a Assets<UnitDefinition>,
+        name: &'a str,
+    ) -> UnitDefinitionRef<'a> {
+        let handle = self.0.get(name).unwrap_or_else(|| {
+            panic!(
+                "no {}/{}.{} to spawn from",
+                UNIT_DEFINITION_FOLDER, name, UNIT_DEFINITION_EXTENSION
+            )
+        });
+        UnitDefinitionRef {
+            id: handle.id(),
+            name,
+            definition: assets.get(handle).unwrap(),
+        }
+    }
+}
+
+//the layout of the map being played
+#[derive(Resource)]
+pub struct CurrentMap(pub Handle<MapLayout>);
+impl CurrentMap {
+    pub fn get<'a>(&self, assets: &'a Assets<MapLayout>) -> Option<&'a MapLayout> {
+        assets.get(&self.0)
+    }
+}
+
+fn init(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(CurrentMap(server.load(MAP_LAYOUT_PATH)));
+    let server = server.clone();
+    let listing = IoTaskPool::get().spawn(async move {
+        let source = server.get_source(AssetSourceId::Default).unwrap();
+        let mut paths = source
+            .reader()
+            .read_directory(Path::new(UNIT_DEFINITION_FOLDER))
+            .await
+            .unwrap_or_else(|error| {
+                panic!(
+                    "couldn't list the {} folder: {}",
+                    UNIT_DEFINITION_FOLDER, error
+                )
+            });
+        let mut handles = Vec::new();
+        while let Some(path) = paths.next().await {
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            if file_name.ends_with(&format!(".{}", UNIT_DEFINITION_EXTENSION)) {
+                handles.push(server.load(path));
+            }
+        }
+        handles
+    });
+    commands.insert_resource(UnitDefinitionFolder::Listing(listing));
+}
+
+fn update_loading(
+    mut commands: Commands,
+    mut folder: ResMut<UnitDefinitionFolder>,
+    map: Res<CurrentMap>,
+    server: Res<AssetServer>,
+    assets: Res<Assets<UnitDefinition>>,
+    definitions: Option<Res<UnitDefinitions>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    //the match can't start without the layout and every definition, so rather than waiting forever report what broke
+    if let LoadState::Failed(error) = server.load_state(&map.0) {
+        error!("{}", error);
+        exit_events.send(AppExit::error());
+        return;
+    }
+    if definitions.is_some() {
+        return;
+    }
+    let handles = match &mut *folder {
+        UnitDefinitionFolder::Listing(listing) => {
+            if let Some(handles) = block_on(poll_once(listing)) {
+                *folder = UnitDefinitionFolder::Loading(handles);
+            }
+            return;
+        }
+        UnitDefinitionFolder::Loading(handles) => handles,
+    };
+    let mut failed = false;
+    for handle in handles.iter() {
+        if let LoadState::Failed(error) = server.load_state(handle) {
+            error!("{}", error);
+            failed = true;
+        }
+    }
+    if failed {
+        exit_events.send(AppExit::error());
+        return;
+    }
+    if !handles
+        .iter()
+        .all(|handle| server.is_loaded_with_dependencies(handle))
+    {
+        return;
+    }
+    let mut definitions = HashMap::new();
+    for handle in handles.iter() {
+        let path = handle.path().unwrap().path();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let name = file_name
+            .strip_suffix(&format!(".{}", UNIT_DEFINITION_EXTENSION))
+            .unwrap();
+        definitions.insert(name.to_string(), handle.clone());
+    }
+    //checked up front rather than the first time each unit spawns, which may be well into the match
+    for (name, archetype) in spawned_units() {
+        let Some(definition) = definitions.get(name).and_then(|handle| assets.get(handle)) else {
+            error!(
+                "no {}/{}.{} to spawn from",
+                UNIT_DEFINITION_FOLDER, name, UNIT_DEFINITION_EXTENSION
+            );
+            failed = true;
+            continue;
+        };
+        for field in definition.missing_fields(archetype) {
+            error!(
+                "{}/{}.{} has no {}, which this unit needs to spawn",
+                UNIT_DEFINITION_FOLDER, name, UNIT_DEFINITION_EXTENSION, field
+            );
+            failed = true;
+        }
+    }
+    if failed {
+        exit_events.send(AppExit::error());
+        return;
+    }
+    commands.insert_resource(UnitDefinitions(definitions));
+}
+
+//applies edited definition files to the units spawned from them, for tuning without restarting
+#[allow(clippy::type_complexity)]
+fn update_reloads(
+    mut events: EventReader<AssetEvent<UnitDefinition>>,
+    assets: Res<Assets<UnitDefinition>>,
+    server: Res<AssetServer>,
+    mut query: Query<(
+        &DefinedBy,
+        &mut Radius,
+        &mut DisplayModel,
+        &mut Health,
+        &mut MaxHealth,
+        Option<&mut MoveSpeed>,
+    )>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let definition = assets.get(*id).unwrap();
+        if let Some(path) = server.get_path(*id) {
+            info!("reloaded {}", path);
+        }
+        for (defined_by, mut radius, mut model, mut health, mut max_health, move_speed) in
+            &mut query
+        {
+            if defined_by.0 != *id {
+                continue;
+            }
+            radius.0 = definition.radius;
+            *model = definition.model;
+            //units keep the same fraction of their health
+            health.0 *= definition.health / max_health.0;
+            max_health.0 = definition.health;
+            if let (Some(mut move_speed), Some(speed)) = (move_speed, definition.move_speed) {
+                *move_speed = MoveSpeed::new(speed);
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::*;
+use bevy::{log::LogPlugin, prelude::*, time::TimeUpdateStrategy};
+use std::time::{Duration, Instant};
+
+pub struct ChecksumPlugin;
+impl Plugin for ChecksumPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Checksum>();
+        app.add_systems(FixedUpdate, update.in_set(ChecksumSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChecksumSet;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+const TEST_COMMAND_INTERVAL: u32 = 40;
+const TEST_ENTITY_OFFSET: usize = 37;
+const TEST_LOADING_TIMEOUT: Duration = Duration::from_secs(60);
+
+//hash of all gameplay state at the end of the last simulated tick, peers with different checksums for the same tick have desynced
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Checksum {
+    pub tick: Option<u32>,
+    pub value: u64,
+}
+
+//fnv-1a, simple and stable across platforms and compiler versions
+struct Hasher(u64);
+impl Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+    fn u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+    fn f32(&mut self, value: f32) {
+        self.u32(value.to_bits());
+    }
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update(
+    query: Query<(
+        &UnitId,
+        &Position,
+        &Facing,
+        Option<&Health>,
+        Option<&MovePosition>,
+        Option<&Order>,
+        Option<&AttackState>,
+        Option<&AttackTarget>,
+        Option<&Gold>,
+        Option<&Experience>,
+    )>,
+    id_query: Query<&UnitId>,
+    mut checksum: ResMut<Checksum>,
+) {
+    let mut units: Vec<_> = query.iter().collect();
+    units.sort_unstable_by_key(|(id, ..)| **id);
+    let mut hasher = Hasher(FNV_OFFSET);
+    for (
+        id,
+        position,
+        facing,
+        health,
+        move_position,
+        order,
+        attack_state,
+        attack_target,
+        gold,
+        experience,
+    ) in units
+    {
+        hasher.u32(id.0);
+        hasher.vec2(position.get());
+        hasher.vec2(facing.0);
+        if let Some(health) = health {
+            hasher.f32(health.0);
+        }
+        if let Some(MovePosition(Some(point))) = move_position {
+            hasher.vec2(*point);
+        }
+        if let Some(order) = order {
+            match *order {
+                Order::Idle => hasher.u32(0),
+                Order::Move(point) => {
+                    hasher.u32(1);
+                    hasher.vec2(point);
+                }
+                Order::AttackMove(point) => {
+                    hasher.u32(2);
+                    hasher.vec2(point);
+                }
+            }
+        }
+        if let Some(state) = attack_state {
+            hasher.u32(state.phase as u32);
+            hasher.u32(state.phase_ticks);
+            hasher.u32(state.cooldown);
+        }
+        //entity ids differ between peers, so targets are hashed by unit id
+        if let Some(AttackTarget(Some(target))) = attack_target {
+            hasher.u32(id_query.get(*target).map_or(0, |id| id.0));
+        }
+        if let Some(gold) = gold {
+            hasher.u32(gold.0);
+        }
+        if let Some(experience) = experience {
+            hasher.u32(experience.0);
+        }
+    }
+    checksum.tick = Some(checksum.tick.map_or(0, |tick| tick + 1));
+    checksum.value = hasher.0;
+    debug!(
+        "tick {} checksum {:016x}",
+        checksum.tick.unwrap(),
+        checksum.value
+    );
+}
+
+//steps two headless apps tick by tick with the same commands and compares their checksums
+//the second app has its entity ids shifted, so anything that depends on them shows up as a desync
+pub fn run_determinism_test(ticks: u32) -> AppExit {
+    let mut apps = [App::new(), App::new()];
+    //logging can only be set up once per process
+    apps[0].add_plugins(LogPlugin::default());
+    for app in &mut apps {
+        app.add_plugins(HeadlessMobaPlugin);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / UPDATE_FIXED_RATE,
+        )));
+        app.finish();
+        app.cleanup();
+    }
+    apps[1]
+        .world_mut()
+        .spawn_batch((0..TEST_ENTITY_OFFSET).map(|_| ()));
+    //loading takes a different number of updates in each app, so each is brought in game on its own
+    let deadline = Instant::now() + TEST_LOADING_TIMEOUT;
+    for app in &mut apps {
+        while *app.world().resource::<State<GameState>>().get() != GameState::InGame {
+            app.update();
+            if let Some(exit) = app.should_exit() {
+                error!("exited while loading");
+                return exit;
+            }
+            if Instant::now() > deadline {
+                error!("still loading after {:?}", TEST_LOADING_TIMEOUT);
+                return AppExit::error();
+            }
+        }
+    }
+    for tick in 0..ticks {
+        //attack-move the player around the map centre, casting skillshots along the way
+        if tick % TEST_COMMAND_INTERVAL == 0 {
+            let angle = tick as f32 / TEST_COMMAND_INTERVAL as f32;
+            let point = Vec2::from_angle(angle) * 200.;
+            for app in &mut apps {
+                app.world_mut().send_event(ActionEvent::AttackMove(point));
+            }
+        } else if tick % TEST_COMMAND_INTERVAL == TEST_COMMAND_INTERVAL / 2 {
+            let point = Vec2::from_angle(tick as f32) * 200.;
+            for app in &mut apps {
+                app.world_mut().send_event(ActionEvent::Skillshot(point));
+            }
+        }
+        for app in &mut apps {
+            app.update();
+            if let Some(exit) = app.should_exit() {
+                error!("exited at tick {}", tick);
+                return exit;
+            }
+        }
+        let [a, b] = apps
+            .each_ref()
+            .map(|app| *app.world().resource::<Checksum>());
+        if a != b {
+            error!(
+                "desync at tick {:?}: {:016x} != {:016x}",
+                a.tick, a.value, b.value
+            );
+            return AppExit::error();
+        }
+        if a.tick.is_none() {
+            error!("no ticks simulated");
+            return AppExit::error();
+        }
+    }
+    info!("checksums matched for {} ticks", ticks);
+    AppExit::Success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_TICKS: u32 = 400;
+
+    #[test]
+    fn simulation_is_deterministic() {
+        assert_eq!(run_determinism_test(TEST_TICKS), AppExit::Success);
+    }
+}
@@ -1,5 +1,21 @@
 pub mod spawn;
 pub use spawn::*;
+pub mod lane;
+pub use lane::*;
+pub mod wave;
+pub use wave::*;
+pub mod attack;
+pub use attack::*;
+pub mod order;
+pub use order::*;
+pub mod death;
+pub use death::*;
+pub mod tower;
+pub use tower::*;
+pub mod camp;
+pub use camp::*;
+pub mod reward;
+pub use reward::*;
 
 use crate::*;
 use bevy::prelude::*;
@@ -8,8 +24,38 @@ use std::{f32::consts::PI, sync::LazyLock};
 pub struct LogicPlugin;
 impl Plugin for LogicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, init.in_set(LogicSet));
-        app.add_systems(FixedUpdate, update_move.in_set(LogicSet));
+        app.init_resource::<WaveManager>();
+        app.init_resource::<UnitIds>();
+        app.add_event::<DamageEvent>();
+        app.add_event::<UnitDied>();
+        app.add_systems(OnEnter(GameState::InGame), init.in_set(LogicSet));
+        app.add_systems(
+            FixedUpdate,
+            (
+                (
+                    update_waves,
+                    update_lanes,
+                    update_camps,
+                    update_camp_members,
+                    update_towers,
+                    update_orders,
+                    update_attacks,
+                )
+                    .chain()
+                    .before(NavigationSet),
+                (update_move, apply_damage, update_deaths, update_rewards)
+                    .chain()
+                    .after(NavigationSet),
+            )
+                .in_set(LogicSet),
+        );
+        app.add_systems(
+            Update,
+            update_layout_reloads
+                .run_if(in_state(GameState::InGame))
+                .run_if(playing_alone)
+                .in_set(LogicSet),
+        );
     }
 }
 
@@ -47,27 +93,71 @@ fn team_transform(team: Team, inverse: bool) -> Transform {
     }
 }
 pub fn reframe_position(position: Vec2, team: Team, to_global: bool) -> Vec2 {
-    (team_transform(team, !to_global) * position.extend(0.)).truncate()
+    quantise_vec2((team_transform(team, !to_global) * position.extend(0.)).truncate())
 }
 
-fn init(mut commands: Commands) {
-    spawn_everything(&mut commands);
-    commands.spawn(Minion::new(Vec2::ZERO, Team::Red));
+//the simulation always advances by exactly this much per tick, regardless of how late the tick runs
+pub const TICK_SECONDS: f32 = (1. / UPDATE_FIXED_RATE) as f32;
+pub fn seconds_to_ticks(seconds: f64) -> u32 {
+    (seconds * UPDATE_FIXED_RATE).round() as u32
 }
 
-fn update_move(mut query: Query<(&mut Transform, &mut MovePosition, &MoveSpeed)>, time: Res<Time>) {
-    for (mut transform, mut move_position, move_speed) in &mut query {
-        if let Some(goal) = move_position.0 {
-            let pos = transform.translation.truncate();
-            let diff = goal - pos;
-            let new_pos = pos + diff.clamp_length_max(move_speed.0 * time.delta_seconds());
-
-            transform.translation = new_pos.extend(0.);
-            transform.rotation = Quat::from_rotation_z(diff.to_angle());
+fn init(
+    mut commands: Commands,
+    mut unit_ids: ResMut<UnitIds>,
+    definitions: Res<UnitDefinitions>,
+    assets: Res<Assets<UnitDefinition>>,
+    map: Res<CurrentMap>,
+    layouts: Res<Assets<MapLayout>>,
+) {
+    spawn_everything(
+        &mut commands,
+        &mut unit_ids,
+        &definitions,
+        &assets,
+        map.get(&layouts).unwrap(),
+    );
+}
 
-            if new_pos == goal {
-                move_position.0 = None;
+fn update_move(
+    mut query: Query<(
+        &mut Position,
+        &mut Facing,
+        &mut MovePosition,
+        &mut Waypoints,
+        &MoveSpeed,
+    )>,
+) {
+    for (mut position, mut facing, mut move_position, mut waypoints, move_speed) in &mut query {
+        if move_position.0.is_none() {
+            continue;
+        }
+        //follow waypoints, carrying leftover distance over each one reached this tick
+        let mut pos = position.get();
+        let mut step = move_speed.per_tick();
+        let mut direction = None;
+        while let Some(waypoint) = waypoints.next() {
+            let diff = waypoint - pos;
+            let distance = diff.length();
+            if distance > 0. {
+                direction = Some(diff / distance);
+            }
+            if distance > step {
+                pos += diff / distance * step;
+                break;
             }
+            pos = waypoint;
+            step -= distance;
+            waypoints.advance();
+        }
+
+        *position = Position::new(pos);
+        if let Some(direction) = direction {
+            facing.0 = direction;
+        }
+
+        if waypoints.next().is_none() {
+            move_position.0 = None;
         }
     }
 }
@@ -0,0 +1,217 @@
+use crate::*;
+use bevy::prelude::*;
+
+const ATTACK_WINDUP_RATIO: f32 = 0.3; //fraction of the attack period spent winding up before the hit registers
+
+//edge-to-edge distance
+#[derive(Component, Clone, Copy)]
+pub struct AttackRange(pub f32);
+
+#[derive(Component, Clone, Copy)]
+pub struct AttackDamage(pub f32);
+
+//attacks per second
+#[derive(Component, Clone, Copy)]
+pub struct AttackSpeed(pub f32);
+impl AttackSpeed {
+    fn period_ticks(&self) -> u32 {
+        seconds_to_ticks(1. / self.0 as f64).max(1)
+    }
+    fn windup_ticks(&self) -> u32 {
+        ((self.period_ticks() as f32 * ATTACK_WINDUP_RATIO).round() as u32).max(1)
+    }
+    fn winddown_ticks(&self) -> u32 {
+        self.period_ticks().saturating_sub(self.windup_ticks())
+    }
+}
+
+//speed of the homing projectile fired on hit, melee attacks land instantly
+#[derive(Component, Clone, Copy, Default)]
+pub struct AttackProjectile(pub Option<f32>);
+
+#[derive(Component, Clone, Copy, Default)]
+pub struct AttackTarget(pub Option<Entity>);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum AttackPhase {
+    #[default]
+    Idle,
+    Windup,
+    Winddown,
+}
+
+//phase_ticks counts down the current phase, cooldown counts down to the next attack and carries over cancelled winddowns
+#[derive(Component, Clone, Copy, Default)]
+pub struct AttackState {
+    pub phase: AttackPhase,
+    pub phase_ticks: u32,
+    pub cooldown: u32,
+}
+impl AttackState {
+    pub fn is_attacking(&self) -> bool {
+        self.phase != AttackPhase::Idle
+    }
+}
+
+#[derive(Event, Clone, Copy)]
+pub struct DamageEvent {
+    pub source: Attacker,
+    pub target: Entity,
+    pub amount: f32,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_attacks(
+    mut commands: Commands,
+    mut attacker_query: Query<(
+        Entity,
+        &UnitId,
+        &Position,
+        &Radius,
+        &AttackRange,
+        &AttackDamage,
+        &AttackSpeed,
+        &AttackProjectile,
+        Option<&Team>,
+        &Archetype,
+        &mut AttackTarget,
+        &mut AttackState,
+        Option<&mut MovePosition>,
+    )>,
+    target_query: Query<(&Position, &Radius), With<Health>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut unit_ids: ResMut<UnitIds>,
+) {
+    //damage events and projectiles are created in a consistent order
+    let mut attackers: Vec<_> = attacker_query
+        .iter()
+        .map(|(entity, id, ..)| (*id, entity))
+        .collect();
+    attackers.sort_unstable();
+    for (_, entity) in attackers {
+        let (
+            _,
+            _,
+            position,
+            radius,
+            range,
+            damage,
+            speed,
+            projectile,
+            team,
+            archetype,
+            mut target,
+            mut state,
+            mut move_position,
+        ) = attacker_query.get_mut(entity).unwrap();
+        state.cooldown = state.cooldown.saturating_sub(1);
+        //drop targets that no longer exist
+        let target_position = target
+            .0
+            .and_then(|target| target_query.get(target).ok())
+            .map(|(target_position, target_radius)| (target_position.get(), target_radius.0));
+        if target_position.is_none() {
+            target.0 = None;
+        }
+        let position = position.get();
+        let in_range = target_position.is_some_and(|(target_position, target_radius)| {
+            position.distance(target_position) - radius.0 - target_radius <= range.0
+        });
+        //any move order issued while attacking shows up as a new MovePosition
+        let move_ordered = move_position.as_ref().is_some_and(|m| m.0.is_some());
+
+        match state.phase {
+            AttackPhase::Windup => {
+                if !in_range || move_ordered {
+                    //cancelled before the hit registered, so the attack is refunded
+                    state.phase = AttackPhase::Idle;
+                    state.cooldown = 0;
+                    continue;
+                }
+                state.phase_ticks -= 1;
+                if state.phase_ticks == 0 {
+                    let source = Attacker {
+                        entity,
+                        team: team.copied(),
+                        archetype: *archetype,
+                    };
+                    if let Some(projectile_speed) = projectile.0 {
+                        let mut projectile = commands.spawn((
+                            ProjectileBundle::new(
+                                position,
+                                source,
+                                damage.0,
+                                projectile_speed,
+                                ProjectileKind::Homing(target.0.unwrap()),
+                            ),
+                            unit_ids.new_id(),
+                        ));
+                        if let Some(team) = team {
+                            projectile.insert(*team);
+                        }
+                    } else {
+                        damage_events.send(DamageEvent {
+                            source,
+                            target: target.0.unwrap(),
+                            amount: damage.0,
+                        });
+                    }
+                    state.phase = AttackPhase::Winddown;
+                    state.phase_ticks = speed.winddown_ticks();
+                    if state.phase_ticks == 0 {
+                        state.phase = AttackPhase::Idle;
+                    }
+                }
+            }
+            AttackPhase::Winddown => {
+                if move_ordered {
+                    //stutter-step, the cooldown keeps running so nothing is lost
+                    state.phase = AttackPhase::Idle;
+                    continue;
+                }
+                state.phase_ticks -= 1;
+                if state.phase_ticks == 0 {
+                    state.phase = AttackPhase::Idle;
+                }
+            }
+            AttackPhase::Idle => {
+                let Some((target_position, _)) = target_position else {
+                    continue;
+                };
+                if in_range {
+                    //hold position while in range, then wind up once the cooldown is over
+                    if let Some(move_position) = move_position.as_mut() {
+                        move_position.0 = None;
+                    }
+                    if state.cooldown == 0 {
+                        state.phase = AttackPhase::Windup;
+                        state.phase_ticks = speed.windup_ticks();
+                        state.cooldown = speed.period_ticks();
+                    }
+                } else if let Some(move_position) = move_position.as_mut() {
+                    //chase
+                    move_position.0 = Some(target_position);
+                } else {
+                    //immobile attackers can't chase
+                    target.0 = None;
+                }
+            }
+        }
+    }
+}
+
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut health_query: Query<(&mut Health, &mut LastAttacker)>,
+) {
+    for event in damage_events.read() {
+        if let Ok((mut health, mut last_attacker)) = health_query.get_mut(event.target) {
+            //overkill is ignored so the killing blow is credited to whoever landed it first
+            if health.0 <= 0. {
+                continue;
+            }
+            health.0 = (health.0 - event.amount).max(0.);
+            last_attacker.0 = Some(event.source);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::*;
+use bevy::prelude::*;
+
+const CAMP_LEASH_RANGE: f32 = 300.; //how far camp units follow an attacker from their spawn point
+const CAMP_HOME_RANGE: f32 = 10.; //close enough to the spawn point to count as back home
+const CAMP_REGEN_SECONDS: f32 = 3.; //time to regenerate from nothing to full health while leashing
+
+//a neutral camp, which spawns its unit and respawns it some time after it dies
+#[derive(Component)]
+pub struct Camp {
+    pub kind: CampKind,
+    pub home: Vec2,
+    pub respawn: u32,
+    member: Option<Entity>,
+    timer: u32,
+}
+impl Camp {
+    //the first unit spawns straight away
+    pub fn new(kind: CampKind, home: Vec2, respawn_seconds: f64) -> Self {
+        Self {
+            kind,
+            home,
+            respawn: seconds_to_ticks(respawn_seconds),
+            member: None,
+            timer: 0,
+        }
+    }
+    pub fn member(&self) -> Option<Entity> {
+        self.member
+    }
+}
+
+//a unit belonging to a camp, idle at home until attacked
+#[derive(Component, Clone, Copy)]
+pub struct CampMember {
+    pub home: Vec2,
+    pub leashing: bool, //returning home and regenerating, deaf to attackers until it arrives
+}
+impl CampMember {
+    pub fn new(home: Vec2) -> Self {
+        Self {
+            home,
+            leashing: false,
+        }
+    }
+}
+
+pub fn update_camps(
+    mut commands: Commands,
+    mut camp_query: Query<(&UnitId, &mut Camp)>,
+    member_query: Query<(), With<CampMember>>,
+    mut unit_ids: ResMut<UnitIds>,
+    definitions: Res<UnitDefinitions>,
+    assets: Res<Assets<UnitDefinition>>,
+) {
+    //camps respawn in a consistent order, so their units get the same ids on every peer
+    let mut camps: Vec<_> = camp_query.iter_mut().collect();
+    camps.sort_unstable_by_key(|(id, _)| **id);
+    for (_, mut camp) in camps {
+        if let Some(member) = camp.member {
+            if member_query.contains(member) {
+                continue;
+            }
+            camp.member = None;
+            camp.timer = camp.respawn;
+        }
+        if camp.timer > 0 {
+            camp.timer -= 1;
+            continue;
+        }
+        let definition = definitions.get(&assets, camp.kind.unit_name());
+        let member = match camp.kind {
+            CampKind::Monster => {
+                commands.spawn((Monster::new(definition, camp.home), unit_ids.new_id()))
+            }
+            CampKind::Demon => {
+                commands.spawn((Demon::new(definition, camp.home), unit_ids.new_id()))
+            }
+        }
+        .id();
+        camp.member = Some(member);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_camp_members(
+    mut query: Query<(
+        &Position,
+        &mut CampMember,
+        &mut Health,
+        &MaxHealth,
+        &mut LastAttacker,
+        &mut AttackTarget,
+        &mut Order,
+    )>,
+    target_query: Query<&Position, With<Health>>,
+) {
+    for (position, mut member, mut health, max_health, mut last_attacker, mut target, mut order) in
+        &mut query
+    {
+        let at_home = position.get().distance(member.home) <= CAMP_HOME_RANGE;
+        if member.leashing {
+            health.0 =
+                (health.0 + max_health.0 * TICK_SECONDS / CAMP_REGEN_SECONDS).min(max_health.0);
+            if at_home {
+                //anything that hit it on the way back is forgotten
+                member.leashing = false;
+                health.0 = max_health.0;
+                last_attacker.0 = None;
+                *order = Order::Idle;
+            }
+            continue;
+        }
+        //fight back against whoever attacked it last
+        if target.0.is_none() {
+            target.0 = last_attacker
+                .0
+                .map(|attacker| attacker.entity)
+                .filter(|attacker| target_query.contains(*attacker));
+        }
+        //give up once it or its target is pulled too far, or once the fight is over away from home
+        let out_of_leash = |point: Vec2| point.distance(member.home) > CAMP_LEASH_RANGE;
+        let pulled = out_of_leash(position.get())
+            || target
+                .0
+                .and_then(|target| target_query.get(target).ok())
+                .is_some_and(|target_position| out_of_leash(target_position.get()));
+        if pulled || (target.0.is_none() && !at_home) {
+            member.leashing = true;
+            target.0 = None;
+            *order = Order::Move(member.home);
+        }
+    }
+}
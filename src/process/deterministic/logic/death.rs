@@ -0,0 +1,55 @@
+use crate::*;
+use bevy::prelude::*;
+
+//a unit dealing damage, taken when the attack is made so it's still known if the unit dies before the attack lands
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Attacker {
+    pub entity: Entity,
+    pub team: Option<Team>,
+    pub archetype: Archetype,
+}
+
+//the last unit to deal damage, credited with the kill
+#[derive(Component, Clone, Copy, Default)]
+pub struct LastAttacker(pub Option<Attacker>);
+
+#[derive(Event, Clone, Copy)]
+pub struct UnitDied {
+    pub entity: Entity,
+    pub killer: Option<Attacker>,
+    pub team: Option<Team>,
+    pub archetype: Archetype,
+    pub position: Vec2,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_deaths(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &UnitId,
+        &Health,
+        &LastAttacker,
+        Option<&Team>,
+        &Archetype,
+        &Position,
+    )>,
+    mut death_events: EventWriter<UnitDied>,
+) {
+    let mut dead: Vec<_> = query
+        .iter()
+        .filter(|(_, _, health, ..)| health.0 <= 0.)
+        .collect();
+    dead.sort_unstable_by_key(|(_, id, ..)| **id);
+    for (entity, _, _, last_attacker, team, archetype, position) in dead {
+        death_events.send(UnitDied {
+            entity,
+            killer: last_attacker.0,
+            team: team.copied(),
+            archetype: *archetype,
+            position: position.get(),
+        });
+        //models are children, healthbars clean themselves up once their anchor is gone
+        commands.entity(entity).despawn_recursive();
+    }
+}
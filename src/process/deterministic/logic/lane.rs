@@ -0,0 +1,28 @@
+use crate::*;
+use bevy::prelude::*;
+
+const LANE_WAYPOINT_RADIUS: f32 = 50.;
+
+//index of the next waypoint along the unit's lane route
+#[derive(Component, Default)]
+pub struct LaneProgress(pub usize);
+
+//routes come from the map layout, in team-local space so both teams share them
+pub fn update_lanes(
+    mut query: Query<(&Position, &Team, &Lane, &mut LaneProgress, &mut Order)>,
+    map: Res<CurrentMap>,
+    layouts: Res<Assets<MapLayout>>,
+) {
+    let layout = map.get(&layouts).unwrap();
+    for (position, team, lane, mut progress, mut order) in &mut query {
+        let route = &layout.lane_routes[*lane as usize];
+        let position = reframe_position(position.get(), *team, false);
+        while progress.0 + 1 < route.len()
+            && position.distance(route[progress.0]) <= LANE_WAYPOINT_RADIUS
+        {
+            progress.0 += 1;
+        }
+        let goal = reframe_position(route[progress.0], *team, true);
+        order.set_if_neq(Order::AttackMove(goal));
+    }
+}
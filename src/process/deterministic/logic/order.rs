@@ -0,0 +1,93 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub const ACQUISITION_RANGE: f32 = 120.; //edge-to-edge distance at which attack-moving units pick up enemies
+
+//what a unit has been told to do, shared by player commands and unit ai
+//replacing the order (as opposed to setting it to an equal value) drops whatever the unit was doing
+#[derive(Component, Clone, Copy, Default, PartialEq, Debug)]
+pub enum Order {
+    #[default]
+    Idle,
+    Move(Vec2),
+    AttackMove(Vec2),
+}
+
+//closest enemy body within range of the given circle, edge-to-edge, optionally counting neutral camps as enemies
+pub fn nearest_enemy(
+    grid: &SpatialGrid,
+    position: Vec2,
+    radius: f32,
+    range: f32,
+    team: Team,
+    neutrals: bool,
+) -> Option<Entity> {
+    grid.query(position, radius + range)
+        .into_iter()
+        .filter(|body| match body.team {
+            Some(body_team) => body_team != team,
+            None => neutrals,
+        })
+        .map(|body| (body.entity, body.position.distance(position) - body.radius))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_orders(
+    mut query: Query<(
+        &Position,
+        &Radius,
+        &Archetype,
+        Option<&Team>,
+        Ref<Order>,
+        &mut MovePosition,
+        &mut AttackTarget,
+    )>,
+    target_query: Query<(&Position, &Radius), With<Health>>,
+    grid: Res<SpatialGrid>,
+) {
+    for (position, radius, archetype, team, order, mut move_position, mut target) in &mut query {
+        //start afresh on new orders
+        if order.is_changed() {
+            target.0 = None;
+            move_position.0 = match *order {
+                Order::Idle => None,
+                Order::Move(point) | Order::AttackMove(point) => Some(point),
+            };
+        }
+        let Order::AttackMove(point) = *order else {
+            continue;
+        };
+        let Some(team) = team else {
+            continue;
+        };
+        let position = position.get();
+        //let go of targets that died or left acquisition range
+        let engaged = target.0.is_some();
+        target.0 = target.0.filter(|target| {
+            target_query
+                .get(*target)
+                .is_ok_and(|(target_position, target_radius)| {
+                    position.distance(target_position.get()) - radius.0 - target_radius.0
+                        <= ACQUISITION_RANGE
+                })
+        });
+        if target.0.is_none() {
+            //only advocates take on camps, minions stick to their lanes
+            let neutrals = *archetype == Archetype::Advocate;
+            target.0 = nearest_enemy(
+                &grid,
+                position,
+                radius.0,
+                ACQUISITION_RANGE,
+                *team,
+                neutrals,
+            );
+            //nothing left to fight, resume the move
+            if target.0.is_none() && engaged {
+                move_position.0 = Some(point);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::*;
+use bevy::prelude::*;
+
+const EXPERIENCE_RANGE: f32 = 500.; //allied advocates within this distance of a death share its experience
+
+//gold for the advocate landing the killing blow and experience shared by nearby allied advocates
+pub struct Bounty {
+    pub gold: u32,
+    pub experience: u32,
+}
+
+fn bounty(archetype: Archetype) -> Option<Bounty> {
+    let (gold, experience) = match archetype {
+        Archetype::Minion => (20, 30),
+        Archetype::Monster => (40, 60),
+        Archetype::Demon => (150, 300),
+        Archetype::Tower => (150, 100),
+        Archetype::Advocate => (300, 200),
+        _ => return None,
+    };
+    Some(Bounty { gold, experience })
+}
+
+pub fn update_rewards(
+    mut death_events: EventReader<UnitDied>,
+    mut gold_query: Query<&mut Gold>,
+    mut advocate_query: Query<(Entity, &UnitId, &Position, &Team, &mut Experience)>,
+) {
+    for event in death_events.read() {
+        let Some(bounty) = bounty(event.archetype) else {
+            continue;
+        };
+        let Some(killer) = event.killer else {
+            continue;
+        };
+        let Some(team) = killer.team else {
+            continue;
+        };
+        if event.team == Some(team) {
+            continue;
+        }
+        //only advocates carry gold, so last hits by minions and towers go unrewarded
+        //an advocate that has died since making the attack misses out on the gold, but not the experience
+        if killer.archetype == Archetype::Advocate {
+            if let Ok(mut gold) = gold_query.get_mut(killer.entity) {
+                gold.0 += bounty.gold;
+            }
+        }
+        let nearby = |position: &Position, advocate_team: &Team| {
+            *advocate_team == team && position.get().distance(event.position) <= EXPERIENCE_RANGE
+        };
+        let mut sharers: Vec<_> = advocate_query
+            .iter()
+            .filter(|(_, _, position, advocate_team, _)| nearby(position, advocate_team))
+            .map(|(entity, id, ..)| (*id, entity))
+            .collect();
+        if sharers.is_empty() {
+            continue;
+        }
+        sharers.sort_unstable();
+        let share = bounty.experience / sharers.len() as u32;
+        //what doesn't split evenly goes to the killer, or the first sharer if the killer isn't one
+        let remainder = bounty.experience % sharers.len() as u32;
+        let receiver = if sharers.iter().any(|(_, entity)| *entity == killer.entity) {
+            killer.entity
+        } else {
+            sharers[0].1
+        };
+        for (_, entity) in sharers {
+            let (.., mut experience) = advocate_query.get_mut(entity).unwrap();
+            experience.0 += share;
+            if entity == receiver {
+                experience.0 += remainder;
+            }
+        }
+    }
+}
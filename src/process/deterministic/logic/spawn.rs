@@ -1,117 +1,121 @@
 use crate::*;
 use bevy::prelude::*;
-use std::{f32::consts::PI, sync::LazyLock};
+use std::f32::consts::PI;
 
-//spawn settings
-const CORE_SPAWN_POSITION: Vec2 = Vec2::splat(300.);
-const SPAWNER_RELATIVE_SPAWN_RADIUS: f32 = 350.;
-static TOPBOT_TOWER_RELATIVE_SPAWN_RADII: LazyLock<Vec<f32>> =
-    LazyLock::new(|| vec![500., 830., 1350.]);
-static MID_TOWER_RELATIVE_SPAWN_RADII: LazyLock<Vec<f32>> =
-    LazyLock::new(|| vec![540., 780., 1000.]);
-const ADVOCATE_SPAWN_NUM: i32 = 5;
-const ADVOCATE_SPAWN_RING_POSITION: Vec2 = Vec2::splat(200.);
-const ADVOCATE_SPAWN_RING_RADIUS: f32 = 50.;
-const ADVOCATE_SPAWN_RING_ROTATION: f32 = PI / 2.;
-const DEMON_SPAWN_POSITION: Vec2 = Vec2::new(1341., 586.);
-static MONSTER_SPAWN_POSITIONS: LazyLock<Vec<Vec2>> = LazyLock::new(|| {
-    vec![
-        Vec2::new(1129., 351.),
-        Vec2::new(1047., 527.),
-        Vec2::new(949., 714.),
-        Vec2::new(521., 878.),
-        Vec2::new(512., 1066.),
-        Vec2::new(293., 1129.),
-    ]
-});
-
-//derived spawn consts/statics
-const BASE_MID_CORNER: Vec2 =
-    Vec2::splat(map::BLENDER_OUTER_WALL_WIDTH + map::BLENDER_LANE_WIDTH / 2.); //some spawns are relative to center-point of overlapping lanes in each base
-static SPAWNER_SPAWN_POSITIONS: LazyLock<Vec<Vec2>> = LazyLock::new(|| {
-    let mut positions = Vec::new();
-    for lane in [Lane::Top, Lane::Mid, Lane::Bot] {
-        let ang = PI / 4. * lane as i32 as f32;
-        let point = Vec2::new(SPAWNER_RELATIVE_SPAWN_RADIUS, 0.).rotate(Vec2::from_angle(ang))
-            + BASE_MID_CORNER;
-        positions.push(point);
-    }
-    positions
-});
-static TOWER_SPAWN_POSITIONS: LazyLock<Vec<Vec2>> = LazyLock::new(|| {
-    let mut positions = Vec::new();
-    let zig_spacing = map::BLENDER_LANE_WIDTH / 2. - TOWER_RADIUS;
-    for lane in [Lane::Top, Lane::Mid, Lane::Bot] {
-        let ang = PI / 4. * lane as i32 as f32;
-        let zig = lane != Lane::Bot;
-        let points = if lane == Lane::Mid {
-            &MID_TOWER_RELATIVE_SPAWN_RADII
-        } else {
-            &TOPBOT_TOWER_RELATIVE_SPAWN_RADII
-        };
-        for zig_point in zig_zag(points, zig, zig_spacing) {
-            let point = zig_point.rotate(Vec2::from_angle(ang)) + BASE_MID_CORNER;
-            positions.push(point);
-        }
-    }
-    positions
-});
-static ADVOCATE_SPAWN_POSITIONS: LazyLock<Vec<Vec2>> = LazyLock::new(|| {
-    let mut positions = Vec::new();
-    for i in 0..ADVOCATE_SPAWN_NUM {
-        let ang = ((2. * PI) / ADVOCATE_SPAWN_NUM as f32) * i as f32;
-        let point = Vec2::new(ADVOCATE_SPAWN_RING_RADIUS, 0.)
-            .rotate(Vec2::from_angle(ang + ADVOCATE_SPAWN_RING_ROTATION))
-            + ADVOCATE_SPAWN_RING_POSITION;
-        positions.push(point);
-    }
-    positions
-});
+//direction each lane leaves the base in, in team-local space
+pub fn lane_direction(lane: Lane) -> Vec2 {
+    Vec2::from_angle(PI / 4. * lane as i32 as f32)
+}
 
-//tower spawning utility
-fn zig_zag(points: &Vec<f32>, zig_first: bool, zig_spacing: f32) -> Vec<Vec2> {
-    let mut zig_zag = Vec::new();
-    for (i, point) in points.iter().enumerate() {
-        zig_zag.push(Vec2::new(
-            *point,
-            if (i % 2 == 0) == zig_first { 1. } else { -1. } * zig_spacing,
-        ));
-    }
-    zig_zag
+//which entry of the map layout a tower or camp was spawned from, so edits to the layout can move it
+#[derive(Component, Clone, Copy)]
+pub struct LayoutPlacement {
+    pub team: Team,
+    pub index: usize,
 }
 
-pub fn spawn_everything(commands: &mut Commands) {
+pub const CORE_UNIT_NAME: &str = "core";
+pub const SPAWNER_UNIT_NAME: &str = "spawner";
+pub const TOWER_UNIT_NAME: &str = "tower";
+pub const ADVOCATE_UNIT_NAME: &str = "advocate";
+
+pub fn spawn_everything(
+    commands: &mut Commands,
+    unit_ids: &mut UnitIds,
+    definitions: &UnitDefinitions,
+    assets: &Assets<UnitDefinition>,
+    layout: &MapLayout,
+) {
+    let core = definitions.get(assets, CORE_UNIT_NAME);
+    let spawner = definitions.get(assets, SPAWNER_UNIT_NAME);
+    let tower = definitions.get(assets, TOWER_UNIT_NAME);
+    let advocate = definitions.get(assets, ADVOCATE_UNIT_NAME);
     for team in [Team::Red, Team::Blue] {
-        commands.spawn(Core::new(
-            logic::reframe_position(CORE_SPAWN_POSITION, team, true),
-            team,
+        commands.spawn((
+            Core::new(core, logic::reframe_position(layout.core, team, true), team),
+            unit_ids.new_id(),
         ));
-        for position in SPAWNER_SPAWN_POSITIONS.iter() {
-            commands.spawn(Spawner::new(
-                logic::reframe_position(*position, team, true),
-                team,
+        for (lane, position) in &layout.spawners {
+            commands.spawn((
+                Spawner::new(
+                    spawner,
+                    logic::reframe_position(*position, team, true),
+                    team,
+                    *lane,
+                ),
+                unit_ids.new_id(),
+            ));
+        }
+        for (index, position) in layout.towers.iter().enumerate() {
+            commands.spawn((
+                Tower::new(tower, logic::reframe_position(*position, team, true), team),
+                LayoutPlacement { team, index },
+                unit_ids.new_id(),
             ));
         }
-        for position in TOWER_SPAWN_POSITIONS.iter() {
-            commands.spawn(Tower::new(
-                logic::reframe_position(*position, team, true),
-                team,
+        //red players come first, then blue
+        for (i, position) in layout.advocates.iter().enumerate() {
+            commands.spawn((
+                Advocate::new(
+                    advocate,
+                    logic::reframe_position(*position, team, true),
+                    team,
+                    PlayerID((team as usize * layout.advocates.len() + i) as i32),
+                ),
+                unit_ids.new_id(),
             ));
         }
-        for (i, position) in ADVOCATE_SPAWN_POSITIONS.iter().enumerate() {
-            commands.spawn(Advocate::new(
-                logic::reframe_position(*position, team, true),
-                team,
-                PlayerID(i as i32),
+        //camps spawn their own units once the game starts
+        for (index, camp) in layout.camps.iter().enumerate() {
+            commands.spawn((
+                Camp::new(
+                    camp.kind,
+                    logic::reframe_position(camp.position, team, true),
+                    camp.respawn,
+                ),
+                LayoutPlacement { team, index },
+                unit_ids.new_id(),
             ));
         }
-        for position in MONSTER_SPAWN_POSITIONS.iter() {
-            commands.spawn(Monster::new(logic::reframe_position(*position, team, true)));
+    }
+}
+
+//moves towers and camps to their placements in an edited map layout, camp units walk over to their new home
+//placements added or removed, and everything else in the layout, only take effect next match
+pub fn update_layout_reloads(
+    mut events: EventReader<AssetEvent<MapLayout>>,
+    map: Res<CurrentMap>,
+    layouts: Res<Assets<MapLayout>>,
+    mut tower_query: Query<(&LayoutPlacement, &mut Position), Without<Camp>>,
+    mut camp_query: Query<(&LayoutPlacement, &mut Camp)>,
+    mut member_query: Query<&mut CampMember>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        if *id != map.0.id() {
+            continue;
+        }
+        let layout = map.get(&layouts).unwrap();
+        info!("reloaded the map layout");
+        for (placement, mut position) in &mut tower_query {
+            if let Some(tower) = layout.towers.get(placement.index) {
+                *position = Position::new(logic::reframe_position(*tower, placement.team, true));
+            }
+        }
+        for (placement, mut camp) in &mut camp_query {
+            let Some(layout_camp) = layout.camps.get(placement.index) else {
+                continue;
+            };
+            camp.home = logic::reframe_position(layout_camp.position, placement.team, true);
+            camp.respawn = seconds_to_ticks(layout_camp.respawn);
+            if let Some(mut member) = camp
+                .member()
+                .and_then(|member| member_query.get_mut(member).ok())
+            {
+                member.home = camp.home;
+            }
         }
-        commands.spawn(Demon::new(logic::reframe_position(
-            DEMON_SPAWN_POSITION,
-            team,
-            true,
-        )));
     }
 }
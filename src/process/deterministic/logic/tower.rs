@@ -0,0 +1,72 @@
+use crate::*;
+use bevy::prelude::*;
+
+//lower is targeted first, anything else is ignored by towers
+fn tower_priority(archetype: Archetype) -> Option<u32> {
+    match archetype {
+        Archetype::Minion => Some(0),
+        Archetype::Advocate => Some(1),
+        _ => None,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_towers(
+    mut tower_query: Query<(
+        &Archetype,
+        &Position,
+        &Radius,
+        &Team,
+        &AttackRange,
+        &mut AttackTarget,
+    )>,
+    unit_query: Query<(&Archetype, &Position, &Radius, Option<&Team>)>,
+    mut damage_events: EventReader<DamageEvent>,
+    grid: Res<SpatialGrid>,
+) {
+    let damage_events: Vec<DamageEvent> = damage_events.read().copied().collect();
+    for (archetype, position, radius, team, range, mut target) in &mut tower_query {
+        if *archetype != Archetype::Tower {
+            continue;
+        }
+        let position = position.get();
+        //archetype and team of the unit, if it's within range of the tower
+        let in_range = |entity: Entity| {
+            let (unit_archetype, unit_position, unit_radius, unit_team) =
+                unit_query.get(entity).ok()?;
+            let distance = position.distance(unit_position.get()) - radius.0 - unit_radius.0;
+            (distance <= range.0).then_some((*unit_archetype, unit_team.copied()))
+        };
+        //enemy advocates that hit an allied advocate under the tower draw its fire immediately
+        let aggro = damage_events.iter().find(|event| {
+            in_range(event.target) == Some((Archetype::Advocate, Some(*team)))
+                && in_range(event.source.entity) == Some((Archetype::Advocate, Some(team.enemy())))
+        });
+        if let Some(event) = aggro {
+            target.0 = Some(event.source.entity);
+            continue;
+        }
+        //otherwise stick with the current target until it dies or leaves range
+        if target
+            .0
+            .and_then(in_range)
+            .is_some_and(|(target_archetype, _)| tower_priority(target_archetype).is_some())
+        {
+            continue;
+        }
+        target.0 = grid
+            .query(position, radius.0 + range.0)
+            .into_iter()
+            .filter(|body| body.team == Some(team.enemy()))
+            .filter_map(|body| {
+                let (body_archetype, ..) = unit_query.get(body.entity).ok()?;
+                let priority = tower_priority(*body_archetype)?;
+                let distance = body.position.distance(position) - body.radius - radius.0;
+                (distance <= range.0).then_some((body.entity, priority, distance))
+            })
+            .min_by(|(_, a_priority, a), (_, b_priority, b)| {
+                a_priority.cmp(b_priority).then(a.total_cmp(b))
+            })
+            .map(|(entity, ..)| entity);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::*;
+use bevy::prelude::*;
+
+const FIRST_WAVE_DELAY_SECONDS: f64 = 5.;
+const WAVE_INTERVAL_SECONDS: f64 = 30.;
+const WAVE_SPACING_SECONDS: f64 = 0.75;
+const WAVE_SPAWN_GAP: f32 = 5.;
+
+//waves start at first_wave and repeat every interval, spawning one minion of the composition per spacing at every spawner
+#[derive(Resource)]
+pub struct WaveManager {
+    pub first_wave: u32,
+    pub interval: u32,
+    pub spacing: u32,
+    pub composition: Vec<MinionKind>,
+    elapsed: u32,
+}
+impl Default for WaveManager {
+    fn default() -> Self {
+        Self {
+            first_wave: seconds_to_ticks(FIRST_WAVE_DELAY_SECONDS),
+            interval: seconds_to_ticks(WAVE_INTERVAL_SECONDS),
+            spacing: seconds_to_ticks(WAVE_SPACING_SECONDS),
+            composition: vec![
+                MinionKind::Melee,
+                MinionKind::Melee,
+                MinionKind::Melee,
+                MinionKind::Ranged,
+                MinionKind::Ranged,
+                MinionKind::Ranged,
+            ],
+            elapsed: 0,
+        }
+    }
+}
+impl WaveManager {
+    pub fn reset(&mut self) {
+        self.elapsed = 0;
+    }
+    //the minion due to spawn this tick, if any
+    fn due(&self) -> Option<MinionKind> {
+        let since_first = self.elapsed.checked_sub(self.first_wave)?;
+        let into_wave = since_first % self.interval.max(1);
+        let index = into_wave / self.spacing.max(1);
+        if index * self.spacing.max(1) != into_wave {
+            return None;
+        }
+        self.composition.get(index as usize).copied()
+    }
+}
+
+pub fn update_waves(
+    mut commands: Commands,
+    mut wave_manager: ResMut<WaveManager>,
+    mut unit_ids: ResMut<UnitIds>,
+    spawner_query: Query<(&UnitId, &Archetype, &Position, &Radius, &Team, &Lane)>,
+    definitions: Res<UnitDefinitions>,
+    assets: Res<Assets<UnitDefinition>>,
+) {
+    if let Some(kind) = wave_manager.due() {
+        let definition = definitions.get(&assets, kind.unit_name());
+        let mut spawners: Vec<_> = spawner_query
+            .iter()
+            .filter(|(_, archetype, ..)| **archetype == Archetype::Spawner)
+            .collect();
+        spawners.sort_unstable_by_key(|(id, ..)| **id);
+        for (_, _, position, radius, team, lane) in spawners {
+            //spawn just outside the spawner, heading down its lane
+            let spawner_position = reframe_position(position.get(), *team, false);
+            let position = spawner_position
+                + lane_direction(*lane) * (radius.0 + definition.radius + WAVE_SPAWN_GAP);
+            commands.spawn((
+                Minion::new(
+                    definition,
+                    reframe_position(position, *team, true),
+                    *team,
+                    *lane,
+                    kind,
+                ),
+                unit_ids.new_id(),
+            ));
+        }
+    }
+    wave_manager.elapsed += 1;
+}
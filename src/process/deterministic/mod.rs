@@ -1,4 +1,14 @@
+pub mod checksum;
+pub use checksum::*;
 pub mod logic;
 pub use logic::*;
+pub mod navigation;
+pub use navigation::*;
 pub mod physics;
 pub use physics::*;
+pub mod projectile;
+pub use projectile::*;
+pub mod terrain;
+pub use terrain::*;
+pub mod vision;
+pub use vision::*;
@@ -0,0 +1,336 @@
+use crate::*;
+use bevy::prelude::*;
+use std::{cmp::Reverse, collections::BinaryHeap, collections::VecDeque};
+
+pub struct NavigationPlugin;
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            init.run_if(resource_exists_and_changed::<Terrain>)
+                .in_set(NavigationSet),
+        );
+        app.add_systems(FixedUpdate, update_paths.in_set(NavigationSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NavigationSet;
+
+const NAV_CLEARANCE_CELLS: i32 = 1; //walls are grown by this many terrain cells so paths keep units off of them
+const NAV_SNAP_SEARCH_CELLS: i32 = 50;
+const NAV_STRAIGHT_COST: u32 = 10;
+const NAV_DIAGONAL_COST: u32 = 14;
+static NAV_NEIGHBOURS: [(IVec2, u32); 8] = [
+    (IVec2::new(1, 0), NAV_STRAIGHT_COST),
+    (IVec2::new(-1, 0), NAV_STRAIGHT_COST),
+    (IVec2::new(0, 1), NAV_STRAIGHT_COST),
+    (IVec2::new(0, -1), NAV_STRAIGHT_COST),
+    (IVec2::new(1, 1), NAV_DIAGONAL_COST),
+    (IVec2::new(1, -1), NAV_DIAGONAL_COST),
+    (IVec2::new(-1, 1), NAV_DIAGONAL_COST),
+    (IVec2::new(-1, -1), NAV_DIAGONAL_COST),
+];
+
+//waypoints towards the current MovePosition goal, recomputed whenever the goal moves to another cell
+#[derive(Component, Default)]
+pub struct Waypoints {
+    goal: Option<Vec2>,
+    points: VecDeque<Vec2>,
+}
+impl Waypoints {
+    pub fn next(&self) -> Option<Vec2> {
+        self.points.front().copied()
+    }
+    //the goal is forgotten on arrival, so ordering the same point again paths back to it
+    pub fn advance(&mut self) {
+        self.points.pop_front();
+        if self.points.is_empty() {
+            self.goal = None;
+        }
+    }
+}
+
+//grid navmesh over the terrain, with walls grown by the navigation clearance
+#[derive(Resource)]
+pub struct NavMesh {
+    navigable: Vec<bool>,
+}
+impl NavMesh {
+    pub fn new(terrain: &Terrain) -> Self {
+        let size = terrain.size();
+        let mut navigable = vec![false; (size.x * size.y) as usize];
+        for x in 0..size.x {
+            for y in 0..size.y {
+                let cell = IVec2::new(x, y);
+                let clear = (-NAV_CLEARANCE_CELLS..=NAV_CLEARANCE_CELLS).all(|dx| {
+                    (-NAV_CLEARANCE_CELLS..=NAV_CLEARANCE_CELLS)
+                        .all(|dy| terrain.is_walkable(cell + IVec2::new(dx, dy)))
+                });
+                navigable[terrain.index(cell).unwrap()] = clear;
+            }
+        }
+        Self { navigable }
+    }
+    pub fn is_navigable(&self, terrain: &Terrain, cell: IVec2) -> bool {
+        terrain
+            .index(cell)
+            .is_some_and(|index| self.navigable[index])
+    }
+    pub fn line_of_sight(&self, terrain: &Terrain, from: Vec2, to: Vec2) -> bool {
+        terrain.traverse(from, to, |cell| self.is_navigable(terrain, cell))
+    }
+    //closest navigable cell by breadth-first search, so that goals inside walls still resolve
+    fn snap(&self, terrain: &Terrain, cell: IVec2) -> Option<IVec2> {
+        let cell = cell.clamp(IVec2::ZERO, terrain.size() - 1);
+        if self.is_navigable(terrain, cell) {
+            return Some(cell);
+        }
+        let mut visited = vec![false; self.navigable.len()];
+        let mut queue = VecDeque::from([cell]);
+        visited[terrain.index(cell).unwrap()] = true;
+        while let Some(current) = queue.pop_front() {
+            if self.is_navigable(terrain, current) {
+                return Some(current);
+            }
+            for (offset, _) in &NAV_NEIGHBOURS[..4] {
+                let next = current + *offset;
+                if (next - cell).abs().max_element() > NAV_SNAP_SEARCH_CELLS {
+                    continue;
+                }
+                if let Some(index) = terrain.index(next) {
+                    if !visited[index] {
+                        visited[index] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+    //a* over the grid followed by string-pulling, so the returned waypoints only turn at corners
+    pub fn find_path(&self, terrain: &Terrain, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let start_cell = self.snap(terrain, terrain.cell(start))?;
+        let goal_cell = self.snap(terrain, terrain.cell(goal))?;
+        let goal = if goal_cell == terrain.cell(goal) {
+            goal
+        } else {
+            terrain.cell_center(goal_cell)
+        };
+        if self.line_of_sight(terrain, start, goal) {
+            return Some(vec![goal]);
+        }
+        let heuristic = |cell: IVec2| {
+            let diff = (goal_cell - cell).abs();
+            let (min, max) = (diff.min_element() as u32, diff.max_element() as u32);
+            NAV_DIAGONAL_COST * min + NAV_STRAIGHT_COST * (max - min)
+        };
+        let width = terrain.size().x as usize;
+        let start_index = terrain.index(start_cell).unwrap();
+        let mut costs = vec![u32::MAX; self.navigable.len()];
+        let mut parents = vec![usize::MAX; self.navigable.len()];
+        let mut open = BinaryHeap::new();
+        costs[start_index] = 0;
+        //ties are broken on cell index so the search is deterministic
+        open.push(Reverse((heuristic(start_cell), start_index)));
+        while let Some(Reverse((_, index))) = open.pop() {
+            let cell = IVec2::new((index % width) as i32, (index / width) as i32);
+            if cell == goal_cell {
+                break;
+            }
+            for (offset, step_cost) in &NAV_NEIGHBOURS {
+                let next = cell + *offset;
+                if !self.is_navigable(terrain, next) {
+                    continue;
+                }
+                //no cutting corners diagonally
+                if offset.x != 0
+                    && offset.y != 0
+                    && !(self.is_navigable(terrain, cell.with_x(next.x))
+                        && self.is_navigable(terrain, cell.with_y(next.y)))
+                {
+                    continue;
+                }
+                let next_index = terrain.index(next).unwrap();
+                let cost = costs[index] + step_cost;
+                if cost < costs[next_index] {
+                    costs[next_index] = cost;
+                    parents[next_index] = index;
+                    open.push(Reverse((cost + heuristic(next), next_index)));
+                }
+            }
+        }
+        //walk back from the goal
+        let goal_index = terrain.index(goal_cell).unwrap();
+        if costs[goal_index] == u32::MAX {
+            return None;
+        }
+        let mut cells = Vec::new();
+        let mut index = goal_index;
+        while index != start_index {
+            cells.push(
+                terrain.cell_center(IVec2::new((index % width) as i32, (index / width) as i32)),
+            );
+            index = parents[index];
+        }
+        cells.reverse();
+        if cells.is_empty() {
+            return Some(vec![goal]);
+        }
+        *cells.last_mut().unwrap() = goal;
+        Some(self.string_pull(terrain, start, &cells))
+    }
+    //drops every point that can be skipped with a straight line from the last one kept
+    fn string_pull(&self, terrain: &Terrain, start: Vec2, points: &[Vec2]) -> Vec<Vec2> {
+        let mut waypoints = Vec::new();
+        let mut anchor = start;
+        for i in 0..points.len() {
+            let next = points.get(i + 1);
+            if next.is_some_and(|next| self.line_of_sight(terrain, anchor, *next)) {
+                continue;
+            }
+            waypoints.push(points[i]);
+            anchor = points[i];
+        }
+        waypoints
+    }
+}
+
+//rebuilt along with the terrain, with every path recomputed over the new navmesh
+fn init(mut commands: Commands, terrain: Res<Terrain>, mut query: Query<&mut Waypoints>) {
+    commands.insert_resource(NavMesh::new(&terrain));
+    for mut waypoints in &mut query {
+        *waypoints = Waypoints::default();
+    }
+}
+
+fn update_paths(
+    mut query: Query<(&Position, &mut MovePosition, &mut Waypoints)>,
+    navmesh: Res<NavMesh>,
+    terrain: Res<Terrain>,
+) {
+    for (position, mut move_position, mut waypoints) in &mut query {
+        if waypoints.goal == move_position.0 {
+            continue;
+        }
+        //goals moving within a navigable cell, like a chased unit, keep the path and only move its end
+        if let (Some(previous), Some(goal)) = (waypoints.goal, move_position.0) {
+            let cell = terrain.cell(goal);
+            if terrain.cell(previous) == cell && navmesh.is_navigable(&terrain, cell) {
+                if let Some(last) = waypoints.points.back_mut() {
+                    *last = goal;
+                    waypoints.goal = Some(goal);
+                    continue;
+                }
+            }
+        }
+        waypoints.goal = move_position.0;
+        waypoints.points.clear();
+        if let Some(goal) = move_position.0 {
+            match navmesh.find_path(&terrain, position.get(), goal) {
+                Some(points) => waypoints.points.extend(points),
+                None => {
+                    //unreachable, give up on the goal
+                    move_position.0 = None;
+                    waypoints.goal = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unobstructed(navmesh: &NavMesh, terrain: &Terrain, start: Vec2, path: &[Vec2]) -> bool {
+        let mut from = start;
+        path.iter().all(|to| {
+            let clear = navmesh.line_of_sight(terrain, from, *to);
+            from = *to;
+            clear
+        })
+    }
+
+    #[test]
+    fn open_ground_is_crossed_in_a_straight_line() {
+        let terrain = test_terrain(false);
+        let navmesh = NavMesh::new(&terrain);
+        let goal = Vec2::new(70., 120.);
+        assert_eq!(
+            navmesh.find_path(&terrain, Vec2::new(30., 50.), goal),
+            Some(vec![goal])
+        );
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let terrain = test_terrain(false);
+        let navmesh = NavMesh::new(&terrain);
+        let (start, goal) = (Vec2::new(50., 50.), Vec2::new(150., 50.));
+        let path = navmesh.find_path(&terrain, start, goal).unwrap();
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.iter().any(|point| point.y > 150.));
+        assert!(unobstructed(&navmesh, &terrain, start, &path));
+    }
+
+    #[test]
+    fn sealed_off_goals_are_unreachable() {
+        let terrain = test_terrain(true);
+        let navmesh = NavMesh::new(&terrain);
+        assert_eq!(
+            navmesh.find_path(&terrain, Vec2::new(50., 50.), Vec2::new(150., 50.)),
+            None
+        );
+    }
+
+    #[test]
+    fn goals_inside_walls_snap_to_a_navigable_cell() {
+        let terrain = test_terrain(false);
+        let navmesh = NavMesh::new(&terrain);
+        let start = Vec2::new(50., 50.);
+        let path = navmesh
+            .find_path(&terrain, start, Vec2::new(100., 50.))
+            .unwrap();
+        let end = *path.last().unwrap();
+        assert!(navmesh.is_navigable(&terrain, terrain.cell(end)));
+        assert!(unobstructed(&navmesh, &terrain, start, &path));
+    }
+
+    #[test]
+    fn string_pulling_keeps_only_the_corners() {
+        let terrain = test_terrain(false);
+        let navmesh = NavMesh::new(&terrain);
+        let points = [
+            Vec2::new(80., 170.),
+            Vec2::new(100., 170.),
+            Vec2::new(120., 170.),
+            Vec2::new(150., 50.),
+        ];
+        assert_eq!(
+            navmesh.string_pull(&terrain, Vec2::new(50., 50.), &points),
+            vec![points[0], points[2], points[3]]
+        );
+        let straight = [
+            Vec2::new(35., 25.),
+            Vec2::new(45., 25.),
+            Vec2::new(55., 25.),
+        ];
+        assert_eq!(
+            navmesh.string_pull(&terrain, Vec2::new(25., 25.), &straight),
+            vec![straight[2]]
+        );
+    }
+
+    #[test]
+    fn arriving_forgets_the_goal() {
+        let goal = Vec2::new(50., 50.);
+        let mut waypoints = Waypoints {
+            goal: Some(goal),
+            points: VecDeque::from([goal]),
+        };
+        waypoints.advance();
+        assert_eq!(waypoints.next(), None);
+        assert_eq!(waypoints.goal, None);
+    }
+}
@@ -1,9 +1,11 @@
-//use crate::*;
+use crate::*;
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>();
         app.add_systems(Startup, init.in_set(PhysicsSet));
         app.add_systems(FixedUpdate, update.in_set(PhysicsSet));
     }
@@ -12,6 +14,139 @@ impl Plugin for PhysicsPlugin {
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PhysicsSet;
 
+const GRID_CELL_SIZE: f32 = 50.;
+const COINCIDENT_PUSH_DIRECTION: Vec2 = Vec2::X;
+
+#[derive(Clone, Copy)]
+pub struct Body {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub radius: f32,
+    pub movable: bool,
+    pub team: Option<Team>,
+}
+
+//broad-phase, rebuilt every tick, bodies are stored once and referenced from every cell their bounds overlap
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<usize>>,
+    bodies: Vec<Body>,
+}
+impl SpatialGrid {
+    fn clear(&mut self) {
+        self.cells.clear();
+        self.bodies.clear();
+    }
+    fn cell_range(position: Vec2, radius: f32) -> (IVec2, IVec2) {
+        let min = ((position - radius) / GRID_CELL_SIZE).floor().as_ivec2();
+        let max = ((position + radius) / GRID_CELL_SIZE).floor().as_ivec2();
+        (min, max)
+    }
+    fn insert(&mut self, body: Body) {
+        let index = self.bodies.len();
+        let (min, max) = Self::cell_range(body.position, body.radius);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+        self.bodies.push(body);
+    }
+    //indices of bodies sharing a cell with the given circle, in insertion order
+    fn candidates(&self, position: Vec2, radius: f32) -> Vec<usize> {
+        let mut candidates = Vec::new();
+        let (min, max) = Self::cell_range(position, radius);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(cell) = self.cells.get(&IVec2::new(x, y)) {
+                    candidates.extend(cell);
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+    //bodies overlapping the given circle, as of the last physics tick
+    pub fn query(&self, position: Vec2, radius: f32) -> Vec<Body> {
+        self.candidates(position, radius)
+            .into_iter()
+            .map(|index| self.bodies[index])
+            .filter(|body| body.position.distance(position) < body.radius + radius)
+            .collect()
+    }
+}
+
 fn init() {}
 
-fn update() {}
+#[allow(clippy::type_complexity)]
+fn update(
+    mut query: Query<
+        (
+            Entity,
+            &UnitId,
+            &mut Position,
+            &Radius,
+            Option<&MoveSpeed>,
+            Option<&Team>,
+        ),
+        Without<Projectile>,
+    >,
+    mut grid: ResMut<SpatialGrid>,
+    terrain: Res<Terrain>,
+) {
+    //broad-phase, in UnitId order so the grid is laid out the same on every peer
+    grid.clear();
+    let mut bodies: Vec<_> = query.iter().collect();
+    bodies.sort_unstable_by_key(|(_, id, ..)| **id);
+    for (entity, _, position, radius, move_speed, team) in bodies {
+        grid.insert(Body {
+            entity,
+            position: position.get(),
+            radius: radius.0,
+            movable: move_speed.is_some(),
+            team: team.copied(),
+        });
+    }
+    //narrow-phase, displacements are accumulated first so the result doesn't depend on resolution order
+    let mut displacements = vec![Vec2::ZERO; grid.bodies.len()];
+    for (i, body) in grid.bodies.iter().enumerate() {
+        if !body.movable {
+            continue;
+        }
+        for j in grid.candidates(body.position, body.radius) {
+            if i == j {
+                continue;
+            }
+            let other = &grid.bodies[j];
+            let diff = body.position - other.position;
+            let overlap = body.radius + other.radius - diff.length();
+            if overlap <= 0. {
+                continue;
+            }
+            let direction = diff.try_normalize().unwrap_or(if i < j {
+                COINCIDENT_PUSH_DIRECTION
+            } else {
+                -COINCIDENT_PUSH_DIRECTION
+            });
+            //movable pairs share the separation, immovable bodies push the full amount
+            let share = if other.movable { 0.5 } else { 1. };
+            displacements[i] += direction * overlap * share;
+        }
+    }
+    //walls are resolved last so that units can never be pushed into them by each other
+    for (index, displacement) in displacements.into_iter().enumerate() {
+        let body = &mut grid.bodies[index];
+        if !body.movable {
+            continue;
+        }
+        let position =
+            quantise_vec2(terrain.resolve_circle(body.position + displacement, body.radius));
+        if position == body.position {
+            continue;
+        }
+        body.position = position;
+        let (_, _, mut position, _, _, _) = query.get_mut(body.entity).unwrap();
+        *position = Position::new(body.position);
+    }
+}
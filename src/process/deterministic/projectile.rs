@@ -0,0 +1,198 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub struct ProjectilePlugin;
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (update_skillshots, update).chain().in_set(ProjectileSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProjectileSet;
+
+pub const PROJECTILE_RADIUS: f32 = 3.;
+pub const PROJECTILE_ELEVATION: f32 = 15.;
+
+#[derive(Clone, Copy)]
+pub enum ProjectileKind {
+    //follows the target until it lands
+    Homing(Entity),
+    //flies straight until it hits anything not on the source's team or runs out of range
+    Skillshot { direction: Vec2, range: f32 },
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Projectile {
+    pub source: Attacker,
+    pub damage: f32,
+    pub speed: f32,
+    pub kind: ProjectileKind,
+}
+
+//an ability firing a skillshot projectile towards a point, cast is set by the player's command and fired next in UnitId order
+#[derive(Component, Clone, Copy)]
+pub struct Skillshot {
+    pub damage: f32,
+    pub speed: f32,
+    pub range: f32,
+    pub cooldown: u32,
+    pub remaining: u32,
+    pub cast: Option<Vec2>,
+}
+impl Skillshot {
+    pub fn new(damage: f32, speed: f32, range: f32, cooldown_seconds: f64) -> Self {
+        Self {
+            damage,
+            speed,
+            range,
+            cooldown: seconds_to_ticks(cooldown_seconds),
+            remaining: 0,
+            cast: None,
+        }
+    }
+}
+
+//distance from the point to the closest point on the segment, and how far along the segment that is
+fn segment_distance(from: Vec2, to: Vec2, point: Vec2) -> (f32, f32) {
+    let segment = to - from;
+    let t = if segment.length_squared() > 0. {
+        ((point - from).dot(segment) / segment.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    (point.distance(from + segment * t), t)
+}
+
+#[allow(clippy::type_complexity)]
+fn update(
+    mut commands: Commands,
+    mut projectile_query: Query<(
+        Entity,
+        &UnitId,
+        &mut Position,
+        &Radius,
+        &mut Projectile,
+        Option<&Team>,
+    )>,
+    target_query: Query<(&Position, &Radius), (With<Health>, Without<Projectile>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    grid: Res<SpatialGrid>,
+) {
+    //damage events go out in a consistent order
+    let mut projectiles: Vec<_> = projectile_query
+        .iter()
+        .map(|(entity, id, ..)| (*id, entity))
+        .collect();
+    projectiles.sort_unstable();
+    for (_, entity) in projectiles {
+        let (_, _, mut position, radius, mut projectile, team) =
+            projectile_query.get_mut(entity).unwrap();
+        let start = position.get();
+        let step = quantise(projectile.speed * TICK_SECONDS);
+        let mut hit = None;
+        match projectile.kind {
+            ProjectileKind::Homing(target) => {
+                let Ok((target_position, target_radius)) = target_query.get(target) else {
+                    //target is gone, fizzle out
+                    commands.entity(entity).despawn_recursive();
+                    continue;
+                };
+                let diff = target_position.get() - start;
+                let distance = diff.length();
+                if distance - target_radius.0 - radius.0 <= step {
+                    hit = Some(target);
+                } else {
+                    *position = Position::new(start + diff / distance * step);
+                }
+            }
+            ProjectileKind::Skillshot { direction, range } => {
+                let step = step.min(range);
+                let end = quantise_vec2(start + direction * step);
+                //swept against bodies from the last physics tick so fast projectiles can't tunnel
+                hit = grid
+                    .query((start + end) / 2., step / 2. + radius.0)
+                    .into_iter()
+                    .filter(|body| {
+                        body.entity != projectile.source.entity
+                            && body.team != team.copied()
+                            && target_query.contains(body.entity)
+                    })
+                    .filter_map(|body| {
+                        let (distance, t) = segment_distance(start, end, body.position);
+                        (distance <= body.radius + radius.0).then_some((body.entity, t))
+                    })
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(entity, _)| entity);
+                if hit.is_none() {
+                    *position = Position::new(end);
+                    projectile.kind = ProjectileKind::Skillshot {
+                        direction,
+                        range: range - step,
+                    };
+                    if range - step <= 0. {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            }
+        }
+        if let Some(target) = hit {
+            damage_events.send(DamageEvent {
+                source: projectile.source,
+                target,
+                amount: projectile.damage,
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+//casts while on cooldown are dropped
+fn update_skillshots(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &UnitId,
+        &Position,
+        &Facing,
+        &Team,
+        &Archetype,
+        &mut Skillshot,
+    )>,
+    mut unit_ids: ResMut<UnitIds>,
+) {
+    let mut casters: Vec<_> = query.iter_mut().collect();
+    casters.sort_unstable_by_key(|(_, id, ..)| **id);
+    for (entity, _, position, facing, team, archetype, mut skillshot) in casters {
+        skillshot.remaining = skillshot.remaining.saturating_sub(1);
+        let Some(point) = skillshot.cast.take() else {
+            continue;
+        };
+        if skillshot.remaining > 0 {
+            continue;
+        }
+        skillshot.remaining = skillshot.cooldown;
+        let direction = (point - position.get()).try_normalize().unwrap_or(facing.0);
+        commands.spawn((
+            ProjectileBundle::new(
+                position.get(),
+                Attacker {
+                    entity,
+                    team: Some(*team),
+                    archetype: *archetype,
+                },
+                skillshot.damage,
+                skillshot.speed,
+                ProjectileKind::Skillshot {
+                    direction,
+                    range: skillshot.range,
+                },
+            ),
+            *team,
+            unit_ids.new_id(),
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+use crate::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+};
+
+pub struct TerrainPlugin;
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Terrain>();
+        app.register_asset_loader(TerrainLoader);
+        app.add_systems(
+            Update,
+            (
+                update.run_if(not(resource_exists::<Terrain>).or_else(playing_alone)),
+                update_loading.run_if(in_state(GameState::Loading)),
+            )
+                .in_set(TerrainSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TerrainSet;
+
+const TERRAIN_WALL_NODE_PREFIX: &str = "Walls";
+const TERRAIN_BRUSH_NODE_PREFIX: &str = "Brushes";
+const TERRAIN_TOP_TOLERANCE: f32 = 0.01;
+pub const TERRAIN_CELL_SIZE: f32 = 10.;
+
+//walkable area and brushes of the map, rasterised from the top faces of the wall and brush meshes in the map gltf
+#[derive(Asset, TypePath, Resource, Clone)]
+pub struct Terrain {
+    origin: Vec2,
+    size: IVec2,
+    walkable: Vec<bool>,
+    brushes: Vec<Option<u32>>,
+}
+impl Terrain {
+    pub fn from_gltf(gltf: &gltf::Gltf) -> Result<Self, TerrainError> {
+        //only the buffer embedded in a .glb is read
+        if gltf
+            .document
+            .buffers()
+            .any(|buffer| !matches!(buffer.source(), gltf::buffer::Source::Bin))
+        {
+            return Err("the map has external buffers, export it as a single .glb".into());
+        }
+        let walls = top_triangles(gltf, TERRAIN_WALL_NODE_PREFIX)?;
+        let brushes = top_triangles(gltf, TERRAIN_BRUSH_NODE_PREFIX)?;
+        let mut terrain = Self::from_walls(&walls)?;
+        terrain.add_brushes(&brushes);
+        Ok(terrain)
+    }
+    //the walls' bounds are the map's bounds, so there's nothing to build without them
+    pub fn from_walls(walls: &[[Vec2; 3]]) -> Result<Self, TerrainError> {
+        if walls.is_empty() {
+            return Err(format!("no {} nodes in the map", TERRAIN_WALL_NODE_PREFIX).into());
+        }
+        let (min, max) = walls.iter().flatten().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), point| (min.min(*point), max.max(*point)),
+        );
+        let size = ((max - min) / TERRAIN_CELL_SIZE).ceil().as_ivec2();
+        let mut terrain = Self {
+            origin: min,
+            size,
+            walkable: vec![true; (size.x * size.y) as usize],
+            brushes: vec![None; (size.x * size.y) as usize],
+        };
+        for index in terrain.covered_cells(walls) {
+            terrain.walkable[index] = false;
+        }
+        Ok(terrain)
+    }
+    //each connected patch of brush cells gets its own id
+    fn add_brushes(&mut self, brushes: &[[Vec2; 3]]) {
+        let mut covered = vec![false; self.brushes.len()];
+        for index in self.covered_cells(brushes) {
+            covered[index] = true;
+        }
+        let mut next_id = 0;
+        for start in 0..covered.len() {
+            if !covered[start] || self.brushes[start].is_some() {
+                continue;
+            }
+            let width = self.size.x as usize;
+            let mut stack = vec![IVec2::new((start % width) as i32, (start / width) as i32)];
+            self.brushes[start] = Some(next_id);
+            while let Some(cell) = stack.pop() {
+                for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                    let next = cell + offset;
+                    if let Some(index) = self.index(next) {
+                        if covered[index] && self.brushes[index].is_none() {
+                            self.brushes[index] = Some(next_id);
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+            next_id += 1;
+        }
+    }
+    //indices of cells whose centers lie inside any of the triangles
+    fn covered_cells(&self, triangles: &[[Vec2; 3]]) -> Vec<usize> {
+        let mut cells = Vec::new();
+        for triangle in triangles {
+            let (min, max) = triangle.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), point| (min.min(*point), max.max(*point)),
+            );
+            let (min, max) = (self.cell(min), self.cell(max));
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    let cell = IVec2::new(x, y);
+                    if let Some(index) = self.index(cell) {
+                        if triangle_contains(triangle, self.cell_center(cell)) {
+                            cells.push(index);
+                        }
+                    }
+                }
+            }
+        }
+        cells
+    }
+    pub fn index(&self, cell: IVec2) -> Option<usize> {
+        if cell.cmplt(IVec2::ZERO).any() || cell.cmpge(self.size).any() {
+            return None;
+        }
+        Some((cell.y * self.size.x + cell.x) as usize)
+    }
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+    pub fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / TERRAIN_CELL_SIZE)
+            .floor()
+            .as_ivec2()
+    }
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * TERRAIN_CELL_SIZE
+    }
+    pub fn cell_rect(&self, cell: IVec2) -> Rect {
+        let min = self.origin + cell.as_vec2() * TERRAIN_CELL_SIZE;
+        Rect::from_corners(min, min + TERRAIN_CELL_SIZE)
+    }
+    //anything outside of the map is treated as wall
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.index(cell).is_some_and(|index| self.walkable[index])
+    }
+    //id of the brush covering the cell, if any
+    pub fn brush(&self, cell: IVec2) -> Option<u32> {
+        self.index(cell).and_then(|index| self.brushes[index])
+    }
+    //visits every cell crossed by the segment in order, stopping early (and returning false) when the visitor does
+    pub fn traverse(&self, from: Vec2, to: Vec2, mut visit: impl FnMut(IVec2) -> bool) -> bool {
+        let start = (from - self.origin) / TERRAIN_CELL_SIZE;
+        let end = (to - self.origin) / TERRAIN_CELL_SIZE;
+        let mut cell = start.floor().as_ivec2();
+        let end_cell = end.floor().as_ivec2();
+        let direction = end - start;
+        let step = IVec2::new(direction.x.signum() as i32, direction.y.signum() as i32);
+        let boundary = |start: f32, cell: i32, direction: f32| {
+            if direction > 0. {
+                (cell as f32 + 1. - start) / direction
+            } else if direction < 0. {
+                (start - cell as f32) / -direction
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut t_max = Vec2::new(
+            boundary(start.x, cell.x, direction.x),
+            boundary(start.y, cell.y, direction.y),
+        );
+        let t_delta = direction.abs().recip();
+        for _ in 0..=(end_cell - cell).abs().element_sum() {
+            if !visit(cell) {
+                return false;
+            }
+            if cell == end_cell {
+                break;
+            }
+            if t_max.x < t_max.y {
+                cell.x += step.x;
+                t_max.x += t_delta.x;
+            } else {
+                cell.y += step.y;
+                t_max.y += t_delta.y;
+            }
+        }
+        true
+    }
+    //pushes a circle out of any wall cells it overlaps, only the component into the wall is removed so movement slides along it
+    pub fn resolve_circle(&self, position: Vec2, radius: f32) -> Vec2 {
+        let mut position = position;
+        let (min, max) = (self.cell(position - radius), self.cell(position + radius));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let cell = IVec2::new(x, y);
+                if self.is_walkable(cell) {
+                    continue;
+                }
+                let rect = self.cell_rect(cell);
+                let closest = position.clamp(rect.min, rect.max);
+                let diff = position - closest;
+                let distance = diff.length();
+                if distance >= radius {
+                    continue;
+                }
+                if distance > 0. {
+                    position += diff / distance * (radius - distance);
+                } else {
+                    //center is inside the wall, leave through the nearest edge
+                    let to_min = position - rect.min;
+                    let to_max = rect.max - position;
+                    let nearest = to_min.min(to_max).min_element();
+                    position = if nearest == to_min.x {
+                        position.with_x(rect.min.x - radius)
+                    } else if nearest == to_max.x {
+                        position.with_x(rect.max.x + radius)
+                    } else if nearest == to_min.y {
+                        position.with_y(rect.min.y - radius)
+                    } else {
+                        position.with_y(rect.max.y + radius)
+                    };
+                }
+            }
+        }
+        position
+    }
+}
+
+//horizontal triangles at the top of each mesh belonging to nodes with the given name prefix
+fn top_triangles(gltf: &gltf::Gltf, node_prefix: &str) -> Result<Vec<[Vec2; 3]>, TerrainError> {
+    let mut triangles = Vec::new();
+    let blob = gltf.blob.as_deref();
+    for node in gltf.document.nodes() {
+        let (Some(mesh), Some(name)) = (node.mesh(), node.name()) else {
+            continue;
+        };
+        if !name.starts_with(node_prefix) {
+            continue;
+        }
+        let matrix = Mat4::from_cols_array_2d(&node.transform().matrix());
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|_| blob);
+            let positions: Vec<Vec3> = reader
+                .read_positions()
+                .ok_or_else(|| format!("{} has a primitive without positions", name))?
+                .map(|position| matrix.transform_point3(Vec3::from(position)))
+                .collect();
+            let indices: Vec<u32> = reader
+                .read_indices()
+                .ok_or_else(|| format!("{} has a primitive without indices", name))?
+                .into_u32()
+                .collect();
+            let top = positions
+                .iter()
+                .map(|position| position.z)
+                .fold(f32::MIN, f32::max);
+            for triangle in indices.chunks_exact(3) {
+                let mut points = [Vec3::ZERO; 3];
+                for (point, index) in points.iter_mut().zip(triangle) {
+                    *point = *positions
+                        .get(*index as usize)
+                        .ok_or_else(|| format!("{} has an index past its positions", name))?;
+                }
+                if points
+                    .iter()
+                    .all(|point| top - point.z <= TERRAIN_TOP_TOLERANCE)
+                {
+                    triangles.push(points.map(|point| point.truncate()));
+                }
+            }
+        }
+    }
+    Ok(triangles)
+}
+
+//inclusive of edges so that cells centered on a seam between two triangles are still covered
+fn triangle_contains(triangle: &[Vec2; 3], point: Vec2) -> bool {
+    let [a, b, c] = *triangle;
+    let d1 = (point - b).perp_dot(a - b);
+    let d2 = (point - c).perp_dot(b - c);
+    let d3 = (point - a).perp_dot(c - a);
+    let negative = d1 < 0. || d2 < 0. || d3 < 0.;
+    let positive = d1 > 0. || d2 > 0. || d3 > 0.;
+    !(negative && positive)
+}
+
+pub type TerrainError = Box<dyn std::error::Error + Send + Sync>;
+
+//builds terrain straight from a map gltf, it claims no extensions so that gltf files still load as scenes
+//unless terrain is asked for by type
+struct TerrainLoader;
+impl AssetLoader for TerrainLoader {
+    type Asset = Terrain;
+    type Settings = ();
+    type Error = TerrainError;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Terrain, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Terrain::from_gltf(&gltf::Gltf::from_slice(&bytes)?)
+    }
+}
+
+#[derive(Resource)]
+struct TerrainHandle {
+    model: String,
+    handle: Handle<Terrain>,
+}
+
+//loaded from the map the layout names once the layout has loaded, and rebuilt whenever either is hot reloaded
+fn update(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    map: Res<CurrentMap>,
+    layouts: Res<Assets<MapLayout>>,
+    handle: Option<Res<TerrainHandle>>,
+    terrains: Res<Assets<Terrain>>,
+    mut events: EventReader<AssetEvent<Terrain>>,
+) {
+    let Some(layout) = map.get(&layouts) else {
+        return;
+    };
+    let Some(handle) = handle.filter(|handle| handle.model == layout.model) else {
+        let handle = server.load(layout.model.clone());
+        //switching back to a model that's still loaded sends no event
+        if let Some(terrain) = terrains.get(&handle) {
+            commands.insert_resource(terrain.clone());
+        }
+        commands.insert_resource(TerrainHandle {
+            model: layout.model.clone(),
+            handle,
+        });
+        return;
+    };
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            if *id != handle.handle.id() {
+                continue;
+            }
+            //the asset can be removed again before its event is read
+            if let Some(terrain) = terrains.get(*id) {
+                commands.insert_resource(terrain.clone());
+            }
+        }
+    }
+}
+
+//the match can't start without terrain, so rather than waiting forever report what broke
+fn update_loading(
+    server: Res<AssetServer>,
+    handle: Option<Res<TerrainHandle>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if let Some(LoadState::Failed(error)) = handle.map(|handle| server.load_state(&handle.handle)) {
+        error!("{}", error);
+        exit_events.send(AppExit::error());
+    }
+}
+
+//a 200x200 map walled down both sides and split by a wall down the middle, with a gap at the top unless sealed
+#[cfg(test)]
+pub fn test_terrain(sealed: bool) -> Terrain {
+    let rect = |min: Vec2, max: Vec2| {
+        [
+            [min, Vec2::new(max.x, min.y), max],
+            [min, max, Vec2::new(min.x, max.y)],
+        ]
+    };
+    let middle_top = if sealed { 200. } else { 150. };
+    let walls = [
+        rect(Vec2::new(0., 0.), Vec2::new(10., 200.)),
+        rect(Vec2::new(190., 0.), Vec2::new(200., 200.)),
+        rect(Vec2::new(90., 0.), Vec2::new(110., middle_top)),
+    ];
+    Terrain::from_walls(&walls.concat()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_block_the_cells_they_cover() {
+        let terrain = test_terrain(false);
+        assert_eq!(terrain.size(), IVec2::new(20, 20));
+        assert!(!terrain.is_walkable(IVec2::new(0, 5)));
+        assert!(!terrain.is_walkable(IVec2::new(9, 5)));
+        assert!(terrain.is_walkable(IVec2::new(5, 5)));
+        assert!(terrain.is_walkable(IVec2::new(9, 17)));
+        assert!(!terrain.is_walkable(IVec2::new(-1, 5)));
+    }
+
+    #[test]
+    fn maps_without_walls_are_rejected() {
+        assert!(Terrain::from_walls(&[]).is_err());
+    }
+
+    #[test]
+    fn traverse_visits_cells_in_order() {
+        let terrain = test_terrain(false);
+        let mut cells = Vec::new();
+        let finished = terrain.traverse(Vec2::new(25., 25.), Vec2::new(75., 25.), |cell| {
+            cells.push(cell);
+            true
+        });
+        assert!(finished);
+        assert_eq!(cells, (2..=7).map(|x| IVec2::new(x, 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn traverse_steps_diagonals_one_axis_at_a_time() {
+        let terrain = test_terrain(false);
+        let mut cells = Vec::new();
+        terrain.traverse(Vec2::new(15., 12.), Vec2::new(47., 44.), |cell| {
+            cells.push(cell);
+            true
+        });
+        assert_eq!(cells.first(), Some(&IVec2::new(1, 1)));
+        assert_eq!(cells.last(), Some(&IVec2::new(4, 4)));
+        assert_eq!(cells.len(), 7);
+        assert!(cells
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).abs().element_sum() == 1));
+    }
+
+    #[test]
+    fn traverse_stops_when_the_visitor_does() {
+        let terrain = test_terrain(false);
+        let mut last = None;
+        let finished = terrain.traverse(Vec2::new(50., 50.), Vec2::new(150., 50.), |cell| {
+            last = Some(cell);
+            terrain.is_walkable(cell)
+        });
+        assert!(!finished);
+        assert_eq!(last, Some(IVec2::new(9, 5)));
+    }
+
+    #[test]
+    fn circles_slide_out_of_walls() {
+        let terrain = test_terrain(false);
+        assert_eq!(
+            terrain.resolve_circle(Vec2::new(85., 50.), 10.),
+            Vec2::new(80., 50.)
+        );
+        assert_eq!(
+            terrain.resolve_circle(Vec2::new(50., 50.), 10.),
+            Vec2::new(50., 50.)
+        );
+    }
+
+    #[test]
+    fn circles_inside_walls_leave_through_the_nearest_edge() {
+        let terrain = test_terrain(false);
+        assert_eq!(
+            terrain.resolve_circle(Vec2::new(92., 45.), 4.),
+            Vec2::new(86., 45.)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub struct VisionPlugin;
+impl Plugin for VisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisionGrid>();
+        app.add_systems(FixedUpdate, update.in_set(VisionSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VisionSet;
+
+#[derive(Component, Clone, Copy)]
+pub struct SightRadius(pub f32);
+
+//whether each team can currently see the entity
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Visible {
+    pub red: bool,
+    pub blue: bool,
+}
+impl Visible {
+    pub fn to(&self, team: Team) -> bool {
+        match team {
+            Team::Red => self.red,
+            Team::Blue => self.blue,
+        }
+    }
+}
+
+//terrain cells each team has vision of, recomputed every tick
+#[derive(Resource, Default)]
+pub struct VisionGrid {
+    red: Vec<bool>,
+    blue: Vec<bool>,
+}
+impl VisionGrid {
+    fn team_cells(&mut self, team: Team) -> &mut Vec<bool> {
+        match team {
+            Team::Red => &mut self.red,
+            Team::Blue => &mut self.blue,
+        }
+    }
+    pub fn is_visible(&self, terrain: &Terrain, team: Team, position: Vec2) -> bool {
+        let cells = match team {
+            Team::Red => &self.red,
+            Team::Blue => &self.blue,
+        };
+        terrain
+            .index(terrain.cell(position))
+            .is_some_and(|index| cells[index])
+    }
+    fn reveal(&mut self, terrain: &Terrain, team: Team, position: Vec2, sight: f32) {
+        let cells = self.team_cells(team);
+        let viewer_brush = terrain.brush(terrain.cell(position));
+        let (min, max) = (
+            terrain.cell(position - sight),
+            terrain.cell(position + sight),
+        );
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let cell = IVec2::new(x, y);
+                let Some(index) = terrain.index(cell) else {
+                    continue;
+                };
+                let center = terrain.cell_center(cell);
+                if cells[index] || center.distance(position) > sight {
+                    continue;
+                }
+                //brushes can only be seen into from inside the same brush
+                if terrain
+                    .brush(cell)
+                    .is_some_and(|brush| Some(brush) != viewer_brush)
+                {
+                    continue;
+                }
+                //walls block sight but are themselves visible
+                cells[index] = terrain.traverse(position, center, |crossed| {
+                    crossed == cell || terrain.is_walkable(crossed)
+                });
+            }
+        }
+    }
+}
+
+fn update(
+    viewer_query: Query<(&Position, &SightRadius, &Team)>,
+    mut visible_query: Query<(&Position, Option<&Team>, &mut Visible)>,
+    mut grid: ResMut<VisionGrid>,
+    terrain: Res<Terrain>,
+) {
+    let size = terrain.size();
+    for team in [Team::Red, Team::Blue] {
+        let cells = grid.team_cells(team);
+        cells.clear();
+        cells.resize((size.x * size.y) as usize, false);
+    }
+    for (position, sight, team) in &viewer_query {
+        grid.reveal(&terrain, *team, position.get(), sight.0);
+    }
+    for (position, team, mut visible) in &mut visible_query {
+        let position = position.get();
+        //allies are always visible to each other
+        visible.set_if_neq(Visible {
+            red: team == Some(&Team::Red) || grid.is_visible(&terrain, Team::Red, position),
+            blue: team == Some(&Team::Blue) || grid.is_visible(&terrain, Team::Blue, position),
+        });
+    }
+}
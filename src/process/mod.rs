@@ -1,6 +1,14 @@
+pub mod definition;
+pub use definition::*;
 pub mod deterministic;
 pub use deterministic::*;
+pub mod network;
+pub use network::*;
 pub mod player;
 pub use player::*;
+pub mod replay;
+pub use replay::*;
+pub mod state;
+pub use state::*;
 pub mod types;
 pub use types::*;
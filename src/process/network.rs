@@ -0,0 +1,406 @@
+use crate::*;
+use bevy::prelude::*;
+use std::{
+    collections::BTreeMap,
+    net::{SocketAddr, UdpSocket},
+};
+
+pub struct NetworkPlugin;
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Lockstep::from_args());
+        app.init_resource::<TickCommands>();
+        app.add_systems(FixedUpdate, update.in_set(NetworkSet));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkSet;
+
+//--bind 127.0.0.1:7000 --player 0 --peer 5@127.0.0.1:7001
+const BIND_ARG: &str = "--bind";
+const PLAYER_ARG: &str = "--player";
+const PEER_ARG: &str = "--peer";
+const DEFAULT_PLAYER_ID: i32 = 0;
+const INPUT_DELAY_TICKS: u32 = 3; //commands issued on a tick are scheduled this many ticks ahead to hide latency
+const MAX_PACKET_SIZE: usize = 65507;
+
+//a player's order for their advocate, as exchanged between peers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Command {
+    Move(Vec2),
+    AttackMove(Vec2),
+    Stop,
+    Skillshot(Vec2),
+}
+impl From<&ActionEvent> for Command {
+    fn from(event: &ActionEvent) -> Self {
+        match *event {
+            ActionEvent::Move(point) => Command::Move(point),
+            ActionEvent::AttackMove(point) => Command::AttackMove(point),
+            ActionEvent::Stop => Command::Stop,
+            ActionEvent::Skillshot(point) => Command::Skillshot(point),
+        }
+    }
+}
+impl Command {
+    //little-endian tag then point, shared by packets and replays
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        let (tag, point) = match *self {
+            Command::Move(point) => (0u8, point),
+            Command::AttackMove(point) => (1, point),
+            Command::Stop => (2, Vec2::ZERO),
+            Command::Skillshot(point) => (3, point),
+        };
+        bytes.push(tag);
+        bytes.extend(point.x.to_le_bytes());
+        bytes.extend(point.y.to_le_bytes());
+    }
+    pub fn decode(reader: &mut Reader) -> Option<Self> {
+        let [tag] = reader.take()?;
+        let point = Vec2::new(
+            f32::from_le_bytes(reader.take()?),
+            f32::from_le_bytes(reader.take()?),
+        );
+        match tag {
+            0 => Some(Command::Move(point)),
+            1 => Some(Command::AttackMove(point)),
+            2 => Some(Command::Stop),
+            3 => Some(Command::Skillshot(point)),
+            _ => None,
+        }
+    }
+}
+
+//every player's commands for the tick being simulated, ordered by player id
+#[derive(Resource, Default)]
+pub struct TickCommands(pub Vec<(i32, Command)>);
+
+struct Peer {
+    player_id: i32,
+    addr: SocketAddr,
+    //commands received from the peer by tick, and the first tick not yet received
+    received: BTreeMap<u32, Vec<Command>>,
+    received_until: u32,
+    //the first of our ticks the peer hasn't received yet
+    acked: u32,
+}
+
+//lockstep state, the simulation only steps once every peer's commands for the next tick have arrived
+#[derive(Resource)]
+pub struct Lockstep {
+    pub player_id: i32,
+    socket: Option<UdpSocket>,
+    peers: Vec<Peer>,
+    input_delay: u32,
+    //counts restarts, so packets left over from an earlier match are ignored
+    match_index: u32,
+    tick: u32,
+    ready: bool,
+    //local commands waiting for their tick, kept until every peer has them
+    scheduled: BTreeMap<u32, Vec<Command>>,
+}
+impl Lockstep {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let values = |name: &str| -> Vec<String> {
+            args.windows(2)
+                .filter(|pair| pair[0] == name)
+                .map(|pair| pair[1].clone())
+                .collect()
+        };
+        let player_id = values(PLAYER_ARG)
+            .first()
+            .map(|id| id.parse().unwrap())
+            .unwrap_or(DEFAULT_PLAYER_ID);
+        let peers: Vec<(i32, SocketAddr)> = values(PEER_ARG)
+            .iter()
+            .map(|peer| {
+                let (id, addr) = peer.split_once('@').unwrap();
+                (id.parse().unwrap(), addr.parse().unwrap())
+            })
+            .collect();
+        let socket = values(BIND_ARG).first().map(|addr| {
+            let socket = UdpSocket::bind(addr).unwrap();
+            socket.set_nonblocking(true).unwrap();
+            socket
+        });
+        Self::new(player_id, socket, peers)
+    }
+    pub fn new(player_id: i32, socket: Option<UdpSocket>, peers: Vec<(i32, SocketAddr)>) -> Self {
+        //nobody to wait on when playing alone, so commands can apply immediately
+        let input_delay = if peers.is_empty() {
+            0
+        } else {
+            INPUT_DELAY_TICKS
+        };
+        Self {
+            player_id,
+            socket,
+            peers: peers
+                .into_iter()
+                .map(|(player_id, addr)| Peer {
+                    player_id,
+                    addr,
+                    received: BTreeMap::new(),
+                    received_until: input_delay,
+                    acked: input_delay,
+                })
+                .collect(),
+            input_delay,
+            match_index: 0,
+            tick: 0,
+            ready: false,
+            scheduled: BTreeMap::new(),
+        }
+    }
+    //starts the next match from its first tick, every peer has to restart along with us
+    pub fn restart(&mut self) {
+        self.match_index += 1;
+        self.tick = 0;
+        self.ready = false;
+        self.scheduled.clear();
+        for peer in &mut self.peers {
+            peer.received.clear();
+            peer.received_until = self.input_delay;
+            peer.acked = self.input_delay;
+        }
+    }
+    pub fn match_index(&self) -> u32 {
+        self.match_index
+    }
+    //the next tick to be simulated
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+    //every player in the match, ordered by player id
+    pub fn roster(&self) -> Vec<i32> {
+        let mut roster: Vec<i32> = self.peers.iter().map(|peer| peer.player_id).collect();
+        roster.push(self.player_id);
+        roster.sort_unstable();
+        roster
+    }
+    fn receive(&mut self) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        while let Ok((length, _)) = socket.recv_from(&mut buffer) {
+            let Some(packet) = Packet::decode(&buffer[..length]) else {
+                continue;
+            };
+            if packet.match_index != self.match_index {
+                continue;
+            }
+            let Some(peer) = self
+                .peers
+                .iter_mut()
+                .find(|peer| peer.player_id == packet.player_id)
+            else {
+                continue;
+            };
+            peer.acked = peer.acked.max(packet.ack);
+            for (tick, commands) in packet.commands {
+                if tick >= peer.received_until {
+                    peer.received.entry(tick).or_insert(commands);
+                }
+            }
+            while peer.received.contains_key(&peer.received_until) {
+                peer.received_until += 1;
+            }
+        }
+    }
+    fn send(&mut self) {
+        let Some(socket) = &self.socket else {
+            return;
+        };
+        for peer in &self.peers {
+            let packet = Packet {
+                player_id: self.player_id,
+                match_index: self.match_index,
+                ack: peer.received_until,
+                commands: self
+                    .scheduled
+                    .range(peer.acked..)
+                    .map(|(tick, commands)| (*tick, commands.clone()))
+                    .collect(),
+            };
+            //dropped packets are covered by resending everything unacknowledged every tick
+            let _ = socket.send_to(&packet.encode(), peer.addr);
+        }
+        //forget commands every peer has and we've already simulated
+        let oldest = self
+            .peers
+            .iter()
+            .map(|peer| peer.acked)
+            .fold(self.tick, u32::min);
+        self.scheduled = self.scheduled.split_off(&oldest);
+    }
+    //takes every player's commands for the next tick, if they've all arrived
+    fn advance(&mut self, local: Vec<Command>) -> Option<Vec<(i32, Command)>> {
+        let tick = self.tick;
+        if self.peers.iter().any(|peer| peer.received_until <= tick) {
+            return None;
+        }
+        self.scheduled.insert(tick + self.input_delay, local);
+        let mut commands: Vec<(i32, Command)> = Vec::new();
+        let own = self.scheduled.get(&tick).cloned().unwrap_or_default();
+        commands.extend(own.into_iter().map(|command| (self.player_id, command)));
+        for peer in &mut self.peers {
+            let received = peer.received.remove(&tick).unwrap_or_default();
+            commands.extend(
+                received
+                    .into_iter()
+                    .map(|command| (peer.player_id, command)),
+            );
+        }
+        //stable, so each player's commands keep the order they were issued in
+        commands.sort_by_key(|(player_id, _)| *player_id);
+        self.tick += 1;
+        Some(commands)
+    }
+}
+
+pub fn lockstep_ready(lockstep: Res<Lockstep>) -> bool {
+    lockstep.ready
+}
+
+//edited assets are only hot reloaded into the local simulation, which would desync it from any peers
+pub fn playing_alone(lockstep: Res<Lockstep>) -> bool {
+    lockstep.peers.is_empty()
+}
+
+//wire format, little-endian: player id, match index, ack, tick count, then per tick the tick, command count and commands
+struct Packet {
+    player_id: i32,
+    match_index: u32,
+    ack: u32,
+    commands: Vec<(u32, Vec<Command>)>,
+}
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(self.player_id.to_le_bytes());
+        bytes.extend(self.match_index.to_le_bytes());
+        bytes.extend(self.ack.to_le_bytes());
+        bytes.extend((self.commands.len() as u32).to_le_bytes());
+        for (tick, commands) in &self.commands {
+            bytes.extend(tick.to_le_bytes());
+            bytes.extend((commands.len() as u32).to_le_bytes());
+            for command in commands {
+                command.encode(&mut bytes);
+            }
+        }
+        bytes
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        let player_id = i32::from_le_bytes(reader.take()?);
+        let match_index = u32::from_le_bytes(reader.take()?);
+        let ack = u32::from_le_bytes(reader.take()?);
+        let mut commands = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take()?) {
+            let tick = u32::from_le_bytes(reader.take()?);
+            let mut tick_commands = Vec::new();
+            for _ in 0..u32::from_le_bytes(reader.take()?) {
+                tick_commands.push(Command::decode(&mut reader)?);
+            }
+            commands.push((tick, tick_commands));
+        }
+        Some(Self {
+            player_id,
+            match_index,
+            ack,
+            commands,
+        })
+    }
+}
+
+pub struct Reader<'a>(pub &'a [u8]);
+impl Reader<'_> {
+    pub fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (taken, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*taken)
+    }
+}
+
+fn update(
+    mut lockstep: ResMut<Lockstep>,
+    mut tick_commands: ResMut<TickCommands>,
+    mut pending: Local<Vec<Command>>,
+    mut action_events: EventReader<ActionEvent>,
+) {
+    //local input is held until the tick it can be scheduled on
+    pending.extend(action_events.read().map(Command::from));
+    lockstep.receive();
+    match lockstep.advance(pending.clone()) {
+        Some(commands) => {
+            pending.clear();
+            tick_commands.0 = commands;
+            lockstep.ready = true;
+        }
+        None => {
+            lockstep.ready = false;
+        }
+    }
+    lockstep.send();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet() -> Packet {
+        Packet {
+            player_id: 5,
+            match_index: 2,
+            ack: 40,
+            commands: vec![
+                (41, vec![Command::Move(Vec2::new(1.5, -2.)), Command::Stop]),
+                (42, Vec::new()),
+                (
+                    43,
+                    vec![
+                        Command::AttackMove(Vec2::new(-300., 12.25)),
+                        Command::Skillshot(Vec2::new(0., 7.)),
+                    ],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn packets_survive_a_round_trip() {
+        let sent = packet();
+        let received = Packet::decode(&sent.encode()).unwrap();
+        assert_eq!(received.player_id, sent.player_id);
+        assert_eq!(received.match_index, sent.match_index);
+        assert_eq!(received.ack, sent.ack);
+        assert_eq!(received.commands, sent.commands);
+    }
+
+    #[test]
+    fn truncated_packets_are_rejected() {
+        let bytes = packet().encode();
+        for length in 0..bytes.len() {
+            assert!(
+                Packet::decode(&bytes[..length]).is_none(),
+                "{} bytes",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_commands_are_rejected() {
+        let mut bytes = Packet {
+            commands: vec![(41, vec![Command::Stop])],
+            ..packet()
+        }
+        .encode();
+        //the command's tag comes right after the tick and command count
+        bytes[24] = 4;
+        assert!(Packet::decode(&bytes).is_none());
+    }
+}
@@ -5,13 +5,12 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<RightPressed>();
-        app.add_systems(Startup, init.in_set(PlayerSet));
+        app.add_event::<ActionEvent>();
+        app.add_systems(OnEnter(GameState::InGame), init.in_set(PlayerSet));
         app.add_systems(FixedUpdate, update.in_set(PlayerSet));
     }
 }
 
-const PLAYER_ID: i32 = 0;
-
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PlayerSet;
 
@@ -21,32 +20,39 @@ pub struct RightPressed(pub bool);
 #[derive(Resource)]
 pub struct Player(pub Entity);
 
-fn init(mut commands: Commands, query: Query<(Entity, &PlayerID)>) {
-    let (player, _) = query
+//kept separately so it outlives the player's advocate
+#[derive(Resource)]
+pub struct PlayerTeam(pub Team);
+
+fn init(mut commands: Commands, query: Query<(Entity, &PlayerID, &Team)>, lockstep: Res<Lockstep>) {
+    let (player, _, team) = query
         .iter()
-        .skip_while(|(_, player_id)| player_id.0 != PLAYER_ID)
+        .skip_while(|(_, player_id, _)| player_id.0 != lockstep.player_id)
         .next()
         .unwrap();
     commands.insert_resource(Player(player));
+    commands.insert_resource(PlayerTeam(*team));
 }
 
+//applies every player's commands for this tick to their advocates
 fn update(
-    player: Res<Player>,
-    mut player_query: Query<&mut MovePosition>,
-    mut action_events: EventReader<ActionEvent>,
+    tick_commands: Res<TickCommands>,
+    mut query: Query<(&PlayerID, &mut Order, &mut Skillshot)>,
 ) {
-    let mut move_position = player_query.get_mut(player.0).unwrap();
-    for event in action_events.read() {
-        match *event {
-            ActionEvent::Move(point) => {
-                move_position.0 = Some(point);
-            }
-            ActionEvent::AttackMove(point) => {
-                move_position.0 = Some(point);
-            }
-            ActionEvent::Stop => {
-                move_position.0 = None;
-            }
+    for (player_id, command) in &tick_commands.0 {
+        //the player's advocate may have died
+        let Some((_, mut order, mut skillshot)) =
+            query.iter_mut().find(|(id, ..)| id.0 == *player_id)
+        else {
+            continue;
+        };
+        //always replace the order so repeated commands restart it
+        match *command {
+            Command::Move(point) => *order = Order::Move(point),
+            Command::AttackMove(point) => *order = Order::AttackMove(point),
+            Command::Stop => *order = Order::Idle,
+            //casting doesn't interrupt the current order
+            Command::Skillshot(point) => skillshot.cast = Some(point),
         }
     }
 }
@@ -0,0 +1,257 @@
+use crate::*;
+use bevy::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |name: &str| -> Option<String> {
+            args.windows(2)
+                .find(|pair| pair[0] == name)
+                .map(|pair| pair[1].clone())
+        };
+        if let Some(path) = value(PLAYBACK_ARG) {
+            //playback stands in for the network, so the match is simulated alone from the recorder's seat
+            let playback = Playback::load(&path);
+            app.insert_resource(Lockstep::new(playback.player_id, None, Vec::new()));
+            app.insert_resource(playback);
+            app.add_systems(
+                OnEnter(GameState::InGame),
+                check_roster
+                    .after(LogicSet)
+                    .before(PlayerSet)
+                    .in_set(ReplaySet),
+            );
+            app.add_systems(FixedUpdate, update_playback.in_set(ReplaySet));
+        } else if let Some(path) = value(RECORD_ARG) {
+            let recording = Recording::create(&path, app.world().resource::<Lockstep>());
+            app.insert_resource(recording);
+            app.add_systems(FixedUpdate, update_recording.in_set(ReplaySet));
+        }
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplaySet;
+
+//--record match.replay, or --replay match.replay to play one back
+const RECORD_ARG: &str = "--record";
+const PLAYBACK_ARG: &str = "--replay";
+const REPLAY_MAGIC: &[u8; 4] = b"MOBA";
+const REPLAY_VERSION: u32 = 1;
+
+//file format, little-endian: magic, version, recording player id, roster count and ids,
+//then until the end of the file per tick with commands the tick, command count and player id and command pairs
+//each match after a restart is written next to the first, e.g. match.replay.2
+#[derive(Resource)]
+pub struct Recording {
+    path: String,
+    writer: BufWriter<File>,
+}
+impl Recording {
+    pub fn create(path: &str, lockstep: &Lockstep) -> Self {
+        let file_path = match lockstep.match_index() {
+            0 => path.to_string(),
+            index => format!("{}.{}", path, index + 1),
+        };
+        let roster = lockstep.roster();
+        let mut bytes = Vec::new();
+        bytes.extend(REPLAY_MAGIC);
+        bytes.extend(REPLAY_VERSION.to_le_bytes());
+        bytes.extend(lockstep.player_id.to_le_bytes());
+        bytes.extend((roster.len() as u32).to_le_bytes());
+        for player_id in roster {
+            bytes.extend(player_id.to_le_bytes());
+        }
+        let mut recording = Self {
+            path: path.to_string(),
+            writer: BufWriter::new(File::create(file_path).unwrap()),
+        };
+        recording.write(&bytes);
+        recording
+    }
+    //starts the next match's file, once the lockstep has restarted
+    pub fn restart(&mut self, lockstep: &Lockstep) {
+        *self = Self::create(&self.path, lockstep);
+    }
+    //flushed straight away so a crash still leaves a replay of everything up to it
+    fn write(&mut self, bytes: &[u8]) {
+        self.writer.write_all(bytes).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+#[derive(Resource)]
+pub struct Playback {
+    pub player_id: i32,
+    pub roster: Vec<i32>,
+    commands: BTreeMap<u32, Vec<(i32, Command)>>,
+}
+impl Playback {
+    pub fn load(path: &str) -> Self {
+        let playback = Self::decode(&std::fs::read(path).unwrap())
+            .unwrap_or_else(|| panic!("{} is not a version {} replay", path, REPLAY_VERSION));
+        assert!(
+            playback.roster.contains(&playback.player_id),
+            "{} was recorded by player {}, who isn't in its roster {:?}",
+            path,
+            playback.player_id,
+            playback.roster
+        );
+        playback
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take()? != *REPLAY_MAGIC || u32::from_le_bytes(reader.take()?) != REPLAY_VERSION {
+            return None;
+        }
+        let player_id = i32::from_le_bytes(reader.take()?);
+        let mut roster = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take()?) {
+            roster.push(i32::from_le_bytes(reader.take()?));
+        }
+        let mut commands = BTreeMap::new();
+        while !reader.0.is_empty() {
+            let tick = u32::from_le_bytes(reader.take()?);
+            let mut tick_commands = Vec::new();
+            for _ in 0..u32::from_le_bytes(reader.take()?) {
+                let player_id = i32::from_le_bytes(reader.take()?);
+                tick_commands.push((player_id, Command::decode(&mut reader)?));
+            }
+            commands.insert(tick, tick_commands);
+        }
+        Some(Self {
+            player_id,
+            roster,
+            commands,
+        })
+    }
+}
+
+//every recorded player has to have an advocate, otherwise the replay is of a different match
+fn check_roster(playback: Res<Playback>, query: Query<&PlayerID>) {
+    let mut player_ids: Vec<i32> = query.iter().map(|player_id| player_id.0).collect();
+    player_ids.sort_unstable();
+    let missing: Vec<i32> = playback
+        .roster
+        .iter()
+        .copied()
+        .filter(|player_id| !player_ids.contains(player_id))
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "replay players {:?} have no advocate, the map only has players {:?}",
+        missing,
+        player_ids
+    );
+}
+
+fn update_recording(
+    mut recording: ResMut<Recording>,
+    lockstep: Res<Lockstep>,
+    tick_commands: Res<TickCommands>,
+) {
+    if tick_commands.0.is_empty() {
+        return;
+    }
+    //the network has already moved on to the next tick
+    let tick = lockstep.tick() - 1;
+    let mut bytes = Vec::new();
+    bytes.extend(tick.to_le_bytes());
+    bytes.extend((tick_commands.0.len() as u32).to_le_bytes());
+    for (player_id, command) in &tick_commands.0 {
+        bytes.extend(player_id.to_le_bytes());
+        command.encode(&mut bytes);
+    }
+    recording.write(&bytes);
+}
+
+//replaces whatever the network gathered with the recorded commands, so live input is ignored
+//the commands are kept, so a restarted match plays the replay again
+fn update_playback(
+    playback: Res<Playback>,
+    lockstep: Res<Lockstep>,
+    mut tick_commands: ResMut<TickCommands>,
+) {
+    let tick = lockstep.tick() - 1;
+    tick_commands.0 = playback.commands.get(&tick).cloned().unwrap_or_default();
+    if playback
+        .commands
+        .last_key_value()
+        .is_some_and(|(last, _)| *last == tick)
+    {
+        info!("replay finished on tick {}", tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(magic: &[u8; 4], version: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(magic);
+        bytes.extend(version.to_le_bytes());
+        bytes.extend(5i32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(0i32.to_le_bytes());
+        bytes.extend(5i32.to_le_bytes());
+        bytes.extend(7u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(0i32.to_le_bytes());
+        Command::Move(Vec2::new(10., -20.)).encode(&mut bytes);
+        bytes.extend(5i32.to_le_bytes());
+        Command::Skillshot(Vec2::new(-3., 4.5)).encode(&mut bytes);
+        bytes.extend(9u32.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(5i32.to_le_bytes());
+        Command::Stop.encode(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn replays_decode_their_header_and_commands() {
+        let playback = Playback::decode(&replay(REPLAY_MAGIC, REPLAY_VERSION)).unwrap();
+        assert_eq!(playback.player_id, 5);
+        assert_eq!(playback.roster, vec![0, 5]);
+        assert_eq!(
+            playback.commands,
+            BTreeMap::from([
+                (
+                    7,
+                    vec![
+                        (0, Command::Move(Vec2::new(10., -20.))),
+                        (5, Command::Skillshot(Vec2::new(-3., 4.5))),
+                    ]
+                ),
+                (9, vec![(5, Command::Stop)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        assert!(Playback::decode(&replay(b"GLTF", REPLAY_VERSION)).is_none());
+        assert!(Playback::decode(&replay(REPLAY_MAGIC, REPLAY_VERSION + 1)).is_none());
+    }
+
+    #[test]
+    fn replays_cut_off_mid_tick_are_rejected() {
+        let bytes = replay(REPLAY_MAGIC, REPLAY_VERSION);
+        //a replay ending after the header or after a whole tick is what a crash leaves behind
+        let tick_ends = [24, 58];
+        for length in 0..bytes.len() {
+            assert_eq!(
+                Playback::decode(&bytes[..length]).is_some(),
+                tick_ends.contains(&length),
+                "{} bytes",
+                length
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::*;
+use bevy::prelude::*;
+
+pub struct GameStatePlugin;
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>();
+        app.add_systems(
+            Update,
+            update_loading
+                .run_if(in_state(GameState::Loading))
+                .run_if(resource_exists::<UnitDefinitions>)
+                .run_if(resource_exists::<NavMesh>)
+                .in_set(GameStateSet),
+        );
+        app.add_systems(FixedUpdate, update.in_set(GameStateSet));
+        app.add_systems(OnEnter(GameState::GameOver), log_result);
+        app.add_systems(OnExit(GameState::GameOver), cleanup);
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameStateSet;
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Loading,
+    InGame,
+    GameOver,
+}
+
+//inserted the tick a core is destroyed, no winner means both cores fell on the same tick
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct GameResult {
+    pub winner: Option<Team>,
+}
+
+//state transitions only happen once per frame, so the result is also checked to stop any further fixed ticks that frame
+pub fn simulation_running(state: Res<State<GameState>>, result: Option<Res<GameResult>>) -> bool {
+    *state.get() == GameState::InGame && result.is_none()
+}
+
+//runs once unit definitions have loaded and the map's terrain and navmesh are built
+fn update_loading(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+fn update(
+    mut commands: Commands,
+    mut death_events: EventReader<UnitDied>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut fallen = Vec::new();
+    for event in death_events.read() {
+        if event.archetype == Archetype::Core {
+            fallen.extend(event.team);
+        }
+    }
+    let Some(loser) = fallen.first().copied() else {
+        return;
+    };
+    let winner = if fallen.iter().all(|team| *team == loser) {
+        Some(loser.enemy())
+    } else {
+        None
+    };
+    commands.insert_resource(GameResult { winner });
+    next_state.set(GameState::GameOver);
+}
+
+fn log_result(result: Res<GameResult>) {
+    match result.winner {
+        Some(team) => info!("game over, {:?} team wins", team),
+        None => info!("game over, draw"),
+    }
+}
+
+//clears the finished match so that re-entering InGame spawns a fresh one
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<Archetype>, With<Projectile>, With<Camp>)>>,
+    mut wave_manager: ResMut<WaveManager>,
+    mut unit_ids: ResMut<UnitIds>,
+    mut lockstep: ResMut<Lockstep>,
+    mut checksum: ResMut<Checksum>,
+    mut tick_commands: ResMut<TickCommands>,
+    recording: Option<ResMut<Recording>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    wave_manager.reset();
+    *unit_ids = UnitIds::default();
+    //the next match starts from the first tick, for peers and replays alike
+    lockstep.restart();
+    *checksum = Checksum::default();
+    tick_commands.0.clear();
+    if let Some(mut recording) = recording {
+        recording.restart(&lockstep);
+    }
+    commands.remove_resource::<GameResult>();
+}
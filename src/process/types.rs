@@ -1,5 +1,6 @@
 use crate::*;
 use bevy::prelude::*;
+use serde::Deserialize;
 
 #[derive(Component, Clone, Copy)]
 pub struct Health(pub f32);
@@ -7,22 +8,86 @@ pub struct Health(pub f32);
 #[derive(Component, Clone, Copy)]
 pub struct MaxHealth(pub f32);
 
+//where the unit is on the ground, the simulation never reads or writes transforms
+//the field is private so that every write goes through new and is quantised
+#[derive(Component, Clone, Copy, PartialEq, Default)]
+pub struct Position(Vec2);
+impl Position {
+    pub fn new(position: Vec2) -> Self {
+        Self(quantise_vec2(position))
+    }
+    pub fn get(&self) -> Vec2 {
+        self.0
+    }
+}
+
+//unit direction the unit last moved in
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct Facing(pub Vec2);
+impl Default for Facing {
+    fn default() -> Self {
+        Self(Vec2::X)
+    }
+}
+
 #[derive(Component, Clone, Copy)]
 pub struct MovePosition(pub Option<Vec2>);
 
+//units per second
 #[derive(Component, Clone, Copy)]
-pub struct MoveSpeed(pub f32);
+pub struct MoveSpeed(f32);
+impl MoveSpeed {
+    pub fn new(speed: f32) -> Self {
+        Self(quantise(speed))
+    }
+    pub fn per_tick(&self) -> f32 {
+        quantise(self.0 * TICK_SECONDS)
+    }
+}
+
+//simulated positions and distances are snapped to this grid after every change,
+//so rounding differences in intermediate math can't accumulate between peers
+pub const QUANTUM: f32 = 1. / 256.;
+pub fn quantise(value: f32) -> f32 {
+    (value / QUANTUM).round() * QUANTUM
+}
+pub fn quantise_vec2(value: Vec2) -> Vec2 {
+    (value / QUANTUM).round() * QUANTUM
+}
 
 #[derive(Component)]
 pub struct Radius(pub f32);
 
-#[derive(Component, Default, PartialEq, Eq, Clone, Copy)]
+//stable identity for simulated entities, since entity ids and query order can differ between peers
+//systems with order-dependent side effects iterate in UnitId order
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct UnitId(pub u32);
+
+//hands out UnitIds in spawn order
+#[derive(Resource, Default)]
+pub struct UnitIds(u32);
+impl UnitIds {
+    pub fn new_id(&mut self) -> UnitId {
+        self.0 += 1;
+        UnitId(self.0)
+    }
+}
+
+#[derive(Component, Default, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Team {
     #[default]
     Red,
     Blue,
 }
-#[derive(Component, Default, PartialEq, Eq, Clone, Copy)]
+impl Team {
+    pub fn enemy(&self) -> Team {
+        match self {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+        }
+    }
+}
+#[derive(Component, Default, PartialEq, Eq, Clone, Copy, Deserialize)]
 pub enum Lane {
     Bot,
     #[default]
@@ -30,6 +95,49 @@ pub enum Lane {
     Top,
 }
 
+//what a unit is, for systems that need to tell bundles apart
+#[derive(Component, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Archetype {
+    Core,
+    Spawner,
+    Tower,
+    Advocate,
+    Minion,
+    Monster,
+    Demon,
+}
+
+#[derive(Component, Default, PartialEq, Eq, Clone, Copy)]
+pub enum MinionKind {
+    #[default]
+    Melee,
+    Ranged,
+}
+impl MinionKind {
+    //name of the unit definition minions of this kind are spawned from
+    pub fn unit_name(&self) -> &'static str {
+        match self {
+            MinionKind::Melee => "melee_minion",
+            MinionKind::Ranged => "ranged_minion",
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CampKind {
+    Monster,
+    Demon,
+}
+impl CampKind {
+    //name of the unit definition the camp's unit is spawned from
+    pub fn unit_name(&self) -> &'static str {
+        match self {
+            CampKind::Monster => "monster",
+            CampKind::Demon => "demon",
+        }
+    }
+}
+
 // pub enum PlayerAction {
 //     Move,
 //     Attack,
@@ -42,12 +150,70 @@ pub enum Lane {
 pub struct MoveBundle {
     move_position: MovePosition,
     move_speed: MoveSpeed,
+    waypoints: Waypoints,
+    order: Order,
 }
 impl MoveBundle {
     pub fn new(move_speed: f32) -> Self {
         Self {
             move_position: MovePosition(None),
-            move_speed: MoveSpeed(move_speed),
+            move_speed: MoveSpeed::new(move_speed),
+            waypoints: Waypoints::default(),
+            order: Order::default(),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct AttackBundle {
+    range: AttackRange,
+    damage: AttackDamage,
+    speed: AttackSpeed,
+    projectile: AttackProjectile,
+    target: AttackTarget,
+    state: AttackState,
+}
+impl AttackBundle {
+    pub fn new(range: f32, damage: f32, speed: f32) -> Self {
+        Self {
+            range: AttackRange(range),
+            damage: AttackDamage(damage),
+            speed: AttackSpeed(speed),
+            projectile: AttackProjectile::default(),
+            target: AttackTarget::default(),
+            state: AttackState::default(),
+        }
+    }
+    pub fn with_projectile(self, projectile_speed: f32) -> Self {
+        Self {
+            projectile: AttackProjectile(Some(projectile_speed)),
+            ..self
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct ProjectileBundle {
+    projectile: Projectile,
+    placement: PlacementBundle,
+    visible: Visible,
+    model: ModelBundle,
+}
+impl ProjectileBundle {
+    pub fn new(pos: Vec2, source: Attacker, damage: f32, speed: f32, kind: ProjectileKind) -> Self {
+        Self {
+            projectile: Projectile {
+                source,
+                damage,
+                speed,
+                kind,
+            },
+            placement: PlacementBundle::new(pos),
+            visible: Visible::default(),
+            model: ModelBundle::new(
+                PROJECTILE_RADIUS,
+                DisplayModel::sphere().with_elevation(PROJECTILE_ELEVATION),
+            ),
         }
     }
 }
@@ -70,6 +236,7 @@ impl ModelBundle {
 pub struct HealthBundle {
     health: Health,
     max_health: MaxHealth,
+    last_attacker: LastAttacker,
     display: DisplayHealthbar,
 }
 impl HealthBundle {
@@ -77,6 +244,7 @@ impl HealthBundle {
         Self {
             health: Health(health),
             max_health: MaxHealth(health),
+            last_attacker: LastAttacker::default(),
             display,
         }
     }
@@ -84,45 +252,57 @@ impl HealthBundle {
 
 #[derive(Bundle)]
 pub struct Unit {
-    spatial: SpatialBundle,
+    archetype: Archetype,
+    defined_by: DefinedBy,
+    placement: PlacementBundle,
+    visible: Visible,
     model: ModelBundle,
     health: HealthBundle,
 }
 impl Unit {
-    pub fn new(
-        pos: Vec2,
-        radius: f32,
-        model: DisplayModel,
-        health: f32,
-        healthbar: DisplayHealthbar,
-    ) -> Self {
+    pub fn new(archetype: Archetype, definition: UnitDefinitionRef, pos: Vec2) -> Self {
         Self {
-            spatial: new_spatial(pos),
-            model: ModelBundle::new(radius, model),
-            health: HealthBundle::new(health, healthbar),
+            archetype,
+            defined_by: DefinedBy(definition.id),
+            placement: PlacementBundle::new(pos),
+            visible: Visible::default(),
+            model: ModelBundle::new(definition.radius, definition.model),
+            health: HealthBundle::new(definition.health, definition.healthbar),
         }
     }
 }
-fn new_spatial(pos: Vec2) -> SpatialBundle {
-    SpatialBundle::from_transform(Transform::from_translation(pos.extend(0.)))
+//simulated placement, plus the transform rendering syncs it into
+#[derive(Bundle)]
+pub struct PlacementBundle {
+    position: Position,
+    facing: Facing,
+    spatial: SpatialBundle,
+}
+impl PlacementBundle {
+    pub fn new(pos: Vec2) -> Self {
+        let position = Position::new(pos);
+        Self {
+            position,
+            facing: Facing::default(),
+            spatial: SpatialBundle::from_transform(Transform::from_translation(
+                position.get().extend(0.),
+            )),
+        }
+    }
 }
 
 #[derive(Bundle)]
 pub struct Core {
     unit: Unit,
     team: Team,
+    sight: SightRadius,
 }
 impl Core {
-    pub fn new(pos: Vec2, team: Team) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2, team: Team) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                50.,
-                DisplayModel::hemisphere(),
-                3400.,
-                DisplayHealthbar::Advanced,
-            ),
+            unit: Unit::new(Archetype::Core, definition, pos),
             team,
+            sight: definition.sight_radius(),
         }
     }
 }
@@ -131,39 +311,34 @@ impl Core {
 pub struct Spawner {
     unit: Unit,
     team: Team,
+    sight: SightRadius,
+    lane: Lane,
 }
 impl Spawner {
-    pub fn new(pos: Vec2, team: Team) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2, team: Team, lane: Lane) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                25.,
-                DisplayModel::hemisphere(),
-                900.,
-                DisplayHealthbar::Advanced,
-            ),
+            unit: Unit::new(Archetype::Spawner, definition, pos),
             team,
+            sight: definition.sight_radius(),
+            lane,
         }
     }
 }
 
-pub const TOWER_RADIUS: f32 = 20.;
 #[derive(Bundle)]
 pub struct Tower {
     unit: Unit,
     team: Team,
+    sight: SightRadius,
+    attack: AttackBundle,
 }
 impl Tower {
-    pub fn new(pos: Vec2, team: Team) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2, team: Team) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                TOWER_RADIUS,
-                DisplayModel::cylinder().with_height_ratio(1.5),
-                500.,
-                DisplayHealthbar::Advanced,
-            ),
+            unit: Unit::new(Archetype::Tower, definition, pos),
             team,
+            sight: definition.sight_radius(),
+            attack: definition.attack_bundle(),
         }
     }
 }
@@ -172,26 +347,36 @@ impl Tower {
 #[derive(Component)]
 pub struct PlayerID(pub i32);
 
+#[derive(Component, Clone, Copy, Default)]
+pub struct Gold(pub u32);
+
+#[derive(Component, Clone, Copy, Default)]
+pub struct Experience(pub u32);
+
 #[derive(Bundle)]
 pub struct Advocate {
     unit: Unit,
     team: Team,
+    sight: SightRadius,
     move_: MoveBundle,
+    attack: AttackBundle,
+    skillshot: Skillshot,
     player_id: PlayerID,
+    gold: Gold,
+    experience: Experience,
 }
 impl Advocate {
-    pub fn new(pos: Vec2, team: Team, player_id: PlayerID) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2, team: Team, player_id: PlayerID) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                12.,
-                DisplayModel::capsule().with_height_ratio(1.75),
-                200.,
-                DisplayHealthbar::Advanced,
-            ),
+            unit: Unit::new(Archetype::Advocate, definition, pos),
             team,
-            move_: MoveBundle::new(100.),
+            sight: definition.sight_radius(),
+            move_: definition.move_bundle(),
+            attack: definition.attack_bundle(),
+            skillshot: definition.skillshot(),
             player_id,
+            gold: Gold::default(),
+            experience: Experience::default(),
         }
     }
 }
@@ -200,14 +385,30 @@ impl Advocate {
 pub struct Minion {
     unit: Unit,
     team: Team,
+    sight: SightRadius,
+    lane: Lane,
+    lane_progress: LaneProgress,
+    kind: MinionKind,
     move_: MoveBundle,
+    attack: AttackBundle,
 }
 impl Minion {
-    pub fn new(pos: Vec2, team: Team) -> Self {
+    pub fn new(
+        definition: UnitDefinitionRef,
+        pos: Vec2,
+        team: Team,
+        lane: Lane,
+        kind: MinionKind,
+    ) -> Self {
         Self {
-            unit: Unit::new(pos, 8., DisplayModel::cube(), 100., DisplayHealthbar::Basic),
+            unit: Unit::new(Archetype::Minion, definition, pos),
             team,
-            move_: MoveBundle::new(50.),
+            sight: definition.sight_radius(),
+            lane,
+            lane_progress: LaneProgress::default(),
+            kind,
+            move_: definition.move_bundle(),
+            attack: definition.attack_bundle(),
         }
     }
 }
@@ -215,19 +416,17 @@ impl Minion {
 #[derive(Bundle)]
 pub struct Monster {
     unit: Unit,
+    camp: CampMember,
     move_: MoveBundle,
+    attack: AttackBundle,
 }
 impl Monster {
-    pub fn new(pos: Vec2) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                10.,
-                DisplayModel::capsule().with_height_ratio(1.75),
-                150.,
-                DisplayHealthbar::Basic,
-            ),
-            move_: MoveBundle::new(200.),
+            unit: Unit::new(Archetype::Monster, definition, pos),
+            camp: CampMember::new(pos),
+            move_: definition.move_bundle(),
+            attack: definition.attack_bundle(),
         }
     }
 }
@@ -235,19 +434,17 @@ impl Monster {
 #[derive(Bundle)]
 pub struct Demon {
     unit: Unit,
+    camp: CampMember,
     move_: MoveBundle,
+    attack: AttackBundle,
 }
 impl Demon {
-    pub fn new(pos: Vec2) -> Self {
+    pub fn new(definition: UnitDefinitionRef, pos: Vec2) -> Self {
         Self {
-            unit: Unit::new(
-                pos,
-                25.,
-                DisplayModel::capsule().with_height_ratio(1.9),
-                1500.,
-                DisplayHealthbar::Advanced,
-            ),
-            move_: MoveBundle::new(300.),
+            unit: Unit::new(Archetype::Demon, definition, pos),
+            camp: CampMember::new(pos),
+            move_: definition.move_bundle(),
+            attack: definition.attack_bundle(),
         }
     }
 }